@@ -5,6 +5,8 @@ pub(crate) enum CliError {
     Io(std::io::Error),
     Fmt(std::fmt::Error),
     Resl(resl::ParseError),
+    Runtime(resl::RuntimeError),
+    Deserialize(resl::DeserializeError),
     Json(serde_json::Error),
     TomlSer(toml::ser::Error),
     TomlDe(toml::de::Error),
@@ -16,6 +18,8 @@ impl std::fmt::Display for CliError {
             CliError::Io(err) => display_io_error(f, err),
             CliError::Fmt(err) => display_fmt_error(f, err),
             CliError::Resl(err) => display_resl_error(f, err),
+            CliError::Runtime(err) => display_runtime_error(f, err),
+            CliError::Deserialize(err) => display_deserialize_error(f, err),
             CliError::Json(err) => display_json_error(f, err),
             CliError::TomlSer(err) => display_toml_ser_error(f, err),
             CliError::TomlDe(err) => display_toml_de_error(f, err),
@@ -144,6 +148,89 @@ fn display_resl_error(f: &mut std::fmt::Formatter<'_>, err: &resl::ParseError) -
     Ok(())
 }
 
+fn display_runtime_error(
+    f: &mut std::fmt::Formatter<'_>,
+    err: &resl::RuntimeError,
+) -> std::fmt::Result {
+    writeln!(
+        f,
+        "{} {}",
+        "Runtime Error:".paint(RED_BOLD_UL),
+        err.kind.to_string().paint(BRIGHT_RED),
+    )?;
+
+    // Location specifier
+
+    let location = format!("line {}, column {}", err.line_number, err.column);
+
+    let line_index = err.line_number.to_string();
+
+    let gutter = line_index.len() + 1;
+
+    for _ in 0..gutter {
+        write!(f, " ")?;
+    }
+
+    writeln!(
+        f,
+        "{}{}{}",
+        "┌─[".paint(WHITE),
+        location.paint(YELLOW_BOLD),
+        "]".paint(WHITE)
+    )?;
+
+    // Empty line
+    for _ in 0..gutter {
+        write!(f, " ")?;
+    }
+
+    writeln!(f, "{}", "│".paint(WHITE))?;
+
+    // Line Location and Content
+
+    writeln!(
+        f,
+        " {}{}{}",
+        line_index.paint(BRIGHT_BLACK_BOLD),
+        "│".paint(WHITE),
+        err.line_content
+    )?;
+
+    // Marker spanning the offending expression, clipped to the end of the line
+    let column_position = err.column - 1;
+    let marker_width = err
+        .span
+        .len()
+        .min(err.line_content.len().saturating_sub(column_position))
+        .max(1);
+
+    for _ in 0..gutter {
+        write!(f, " ")?;
+    }
+
+    write!(f, "{}", "└".paint(WHITE))?;
+
+    for _ in 0..column_position {
+        write!(f, " ")?;
+    }
+
+    writeln!(f, "{}", "^".repeat(marker_width).paint(CYAN_BOLD))?;
+
+    Ok(())
+}
+
+fn display_deserialize_error(
+    f: &mut std::fmt::Formatter<'_>,
+    err: &resl::DeserializeError,
+) -> std::fmt::Result {
+    write!(
+        f,
+        "{} {}",
+        "Deserialize Error:".paint(RED_BOLD_UL),
+        err.paint(BRIGHT_RED)
+    )
+}
+
 fn display_json_error(
     f: &mut std::fmt::Formatter<'_>,
     err: &serde_json::Error,
@@ -198,6 +285,22 @@ impl From<resl::ParseError> for CliError {
     }
 }
 
+impl From<resl::RuntimeError> for CliError {
+    fn from(err: resl::RuntimeError) -> Self {
+        CliError::Runtime(err)
+    }
+}
+
+impl From<resl::Error> for CliError {
+    fn from(err: resl::Error) -> Self {
+        match err {
+            resl::Error::Parse(err) => CliError::Resl(err),
+            resl::Error::Runtime(err) => CliError::Runtime(err),
+            resl::Error::Deserialize(err) => CliError::Deserialize(err),
+        }
+    }
+}
+
 impl From<serde_json::Error> for CliError {
     fn from(err: serde_json::Error) -> Self {
         CliError::Json(err)
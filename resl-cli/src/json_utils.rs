@@ -27,8 +27,9 @@ pub(crate) fn resl_to_json(resl_value: ReslValue) -> JsonValue {
         ReslValue::Null => JsonValue::Null,
         ReslValue::Boolean(b) => JsonValue::Bool(b),
         ReslValue::Integer(i) => JsonValue::Number(serde_json::Number::from(i)),
+        // JSON cannot represent infinities or NaN
         ReslValue::Float(f) => {
-            JsonValue::Number(serde_json::Number::from_f64(f).expect("Conversion should be valid"))
+            serde_json::Number::from_f64(f).map_or(JsonValue::Null, JsonValue::Number)
         }
         ReslValue::String(s) => JsonValue::String(s.to_owned()),
 
@@ -42,5 +43,7 @@ pub(crate) fn resl_to_json(resl_value: ReslValue) -> JsonValue {
                 .map(|(k, v)| (k.to_owned(), resl_to_json(v.to_owned())))
                 .collect(),
         ),
+        // JSON has no functions, they are exported like null
+        _ => JsonValue::Null,
     }
 }
@@ -17,7 +17,6 @@ use std::{
 };
 
 use clap::{Parser, Subcommand, ValueEnum};
-use resl::evaluate_and_format;
 
 use crate::{
     error::CliError,
@@ -42,6 +41,14 @@ struct Cli {
     /// The format style for output
     #[arg(short, long, global = true)]
     pretty: bool,
+
+    /// Fail on runtime errors instead of evaluating them to null
+    #[arg(short, long, global = true)]
+    strict: bool,
+
+    /// Allow `env` to read the given environment variable (repeatable)
+    #[arg(long = "allow-env", value_name = "NAME", global = true)]
+    allow_env: Vec<String>,
 }
 
 #[derive(Debug, Clone, Subcommand)]
@@ -85,7 +92,7 @@ fn main() {
 fn run() -> anyhow::Result<(), CliError> {
     let cli = Cli::parse();
 
-    let input = match cli.input {
+    let input = match &cli.input {
         Some(input_path) => fs::read_to_string(input_path)?,
         None => {
             let mut input = String::new();
@@ -96,6 +103,21 @@ fn run() -> anyhow::Result<(), CliError> {
 
     let pretty = cli.pretty;
 
+    // Imports are resolved relative to the input file, or the working directory for stdin
+    let base_dir = cli
+        .input
+        .as_deref()
+        .and_then(|input_path| input_path.parent())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let engine = resl::Engine::new()
+        .strict(cli.strict)
+        .allow_envs(&cli.allow_env)
+        .resolver(resl::FileResolver::new(base_dir));
+
+    let evaluate = |input: &str| -> Result<resl::Value, CliError> { Ok(engine.evaluate(input)?) };
+
     match cli.command {
         Command::Format => match cli.output {
             Some(output_path) => {
@@ -108,18 +130,22 @@ fn run() -> anyhow::Result<(), CliError> {
                 resl::format(&input, &mut IoFmtAdapter(&mut stdout), pretty)?;
             }
         },
-        Command::Evaluate => match cli.output {
-            Some(output_path) => {
-                let mut file = fs::File::create(output_path)?;
-                evaluate_and_format(&input, &mut IoFmtAdapter(&mut file), pretty)?;
-            }
-            None => {
-                let mut stdout = io::stdout();
-                evaluate_and_format(&input, &mut IoFmtAdapter(&mut stdout), pretty)?;
+        Command::Evaluate => {
+            let resl_value = evaluate(&input)?;
+
+            match cli.output {
+                Some(output_path) => {
+                    let mut file = fs::File::create(output_path)?;
+                    resl_value.write_formatted(&mut IoFmtAdapter(&mut file), pretty)?;
+                }
+                None => {
+                    let mut stdout = io::stdout();
+                    resl_value.write_formatted(&mut IoFmtAdapter(&mut stdout), pretty)?;
+                }
             }
-        },
+        }
         Command::Export { to } => {
-            let resl_value = resl::evaluate(&input)?;
+            let resl_value = evaluate(&input)?;
             match to {
                 DataFormat::Json => {
                     let json_value = resl_to_json(resl_value);
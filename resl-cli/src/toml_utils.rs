@@ -29,5 +29,7 @@ pub(crate) fn resl_to_toml(value: ReslValue) -> TomlValue {
         ReslValue::Map(map) => {
             TomlValue::Table(map.into_iter().map(|(k, v)| (k, resl_to_toml(v))).collect())
         }
+        // TOML has no functions, they are exported like null
+        _ => TomlValue::String(String::new()),
     }
 }
@@ -167,6 +167,8 @@ fn to_resl_value(val: &Value) -> *mut ReslValue {
                 },
             })
         }
+        // Functions cannot be called from C, so they are passed as null
+        _ => return to_resl_value(&Value::Null),
     };
     Box::into_raw(boxed)
 }
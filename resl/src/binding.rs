@@ -1,4 +1,4 @@
-use crate::{expr::Expr, state::FmtState, value::Value};
+use crate::{expr::Expr, span::Span, state::FmtState, value::Value};
 
 /// Represents a variable binding in the evaluation context.
 ///
@@ -14,6 +14,14 @@ pub enum Binding {
 }
 
 impl Binding {
+    /// Returns the span of the bound expression, or an empty span for cached values.
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            Binding::Expr(expr) => expr.span(),
+            Binding::Cached(_) => Span::default(),
+        }
+    }
+
     pub(crate) fn format<W: std::fmt::Write>(
         &self,
         writer: &mut W,
@@ -1,29 +1,38 @@
 use winnow::{
     ModalResult, Parser,
     combinator::{alt, cut_err, delimited, fail, repeat, separated_pair, terminated},
+    stream::Location,
 };
 
 use crate::{
     StatefulInput,
+    binding::Binding,
+    comment::Attachments,
     context::Context,
-    expr::Expr,
-    function::Fn,
+    document::NameKind,
+    error::EvalResult,
+    expr::{Expr, ExprKind},
     ident::Ident,
     macros::{exp_char, exp_desc, label},
-    state::{EvalState, FmtState},
-    utils::{delimited_multispace0, write_indent},
-    value::Value,
+    span::{Span, Spanned},
+    state::{CtxState, EvalState, FmtState},
+    utils::{delimited_multispace0, located, with_bitwise_or, write_indent},
 };
 
 /// Block expression with scoped variables.
 #[derive(Debug, Clone)]
 pub struct Block {
     ctx_idx: usize,
+    /// Spans of the bound names, in the order of the context's bindings
+    names: Vec<Span>,
     return_expr: Box<Expr>,
 }
 
 impl Block {
     pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
+        // Store the start of the block for its span
+        let start = input.current_token_start();
+
         // Store the current active context index before parsing the block
         let current_ctx_idx = input.state.active_ctx_idx();
 
@@ -40,42 +49,36 @@ impl Block {
 
         // Parse the block without unwrapping the result
         // This allows restoring the state variables later
-        let parse_result = delimited(
-            '{',
-            (
-                // Assignments
-                delimited_multispace0(alt((
-                    repeat(
-                        // One or more assignments
-                        1..,
-                        delimited_multispace0(terminated(
-                            separated_pair(
-                                Ident::parse_ident,
-                                delimited_multispace0('='),
-                                alt((
-                                    Expr::parse,
-                                    // Allow function definitions as well
-                                    Fn::parse,
-                                    cut_err(fail).context(exp_desc!("an expression")),
-                                )),
-                            ),
-                            cut_err(';').context(exp_char!(';')),
-                        )),
-                    ),
-                    cut_err(fail).context(exp_desc!("at least one assignment")),
-                ))),
-                // Required final expression in the block
-                Expr::require_parse.map(Box::new),
-            ),
-            cut_err('}').context(exp_char!('}')),
+        let parse_result = with_bitwise_or(
+            true,
+            delimited(
+                '{',
+                (
+                    // Assignments
+                    delimited_multispace0(alt((
+                        repeat(
+                            // One or more assignments
+                            1..,
+                            delimited_multispace0(terminated(
+                                Self::parse_assignment,
+                                cut_err(';').context(exp_char!(';')),
+                            )),
+                        ),
+                        cut_err(fail).context(exp_desc!("at least one assignment")),
+                    ))),
+                    // Required final expression in the block
+                    Expr::require_parse.map(Box::new),
+                ),
+                cut_err('}').context(exp_char!('}')),
+            )
+            .context(label!("block expression")),
         )
-        .context(label!("block expression"))
         .parse_next(input);
 
         // Restore active context to previous one
         input.state.set_active_ctx(current_ctx_idx);
 
-        let (assignments, return_expr): (Vec<(Ident, Expr)>, Box<Expr>) = parse_result
+        let (assignments, return_expr): (Vec<(Spanned<Ident>, Expr)>, Box<Expr>) = parse_result
             .inspect_err(|_| {
                 // Returned backtrack error during parsing
 
@@ -83,36 +86,75 @@ impl Block {
                 input.state.decrement_avail_ctx_idx();
             })?;
 
+        // A name bound again keeps the place of its first binding, as in the context
+        let names = assignments
+            .iter()
+            .map(|(name, _)| (name.node.clone(), name.span))
+            .collect::<indexmap::IndexMap<_, _>>()
+            .into_values()
+            .collect();
+
         // Create a new context for this block with the parsed assignments
-        let ctx = Context::from_iter(Some(current_ctx_idx), assignments);
+        let ctx = Context::from_iter(
+            Some(current_ctx_idx),
+            assignments
+                .into_iter()
+                .map(|(name, expr)| (name.node, expr)),
+        );
 
         // Place the new context at its index in the state's contexts
         input.state.place_ctx(ctx_idx, ctx);
 
-        Ok(Expr::Block(Self {
-            ctx_idx,
-            return_expr,
-        }))
+        Ok(Expr::new(
+            ExprKind::Block(Self {
+                ctx_idx,
+                names,
+                return_expr,
+            }),
+            Span::new(start, input.previous_token_end()),
+        ))
     }
 
-    pub(crate) fn evaluate(self, state: &mut EvalState) -> Value {
-        // Save the index of the current ctx
-        let current_ctx_idx = state.active_ctx_idx();
+    /// Parses a `name = expr` assignment, recording the name once it is known to be bound.
+    fn parse_assignment(input: &mut StatefulInput) -> ModalResult<(Spanned<Ident>, Expr)> {
+        let (name, expr) = separated_pair(
+            located(Ident::parse_ident),
+            delimited_multispace0('='),
+            alt((
+                Expr::parse,
+                cut_err(fail).context(exp_desc!("an expression")),
+            )),
+        )
+        .parse_next(input)?;
 
-        // Set self as the active context
-        state.set_active_ctx(self.ctx_idx);
+        input.state.record_ident(&name, NameKind::Binding);
 
-        // Evaluate the expression in the context of this block
-        let value = self.return_expr.evaluate(state);
+        Ok((name, expr))
+    }
 
-        // Reset active context to parent context
-        state.set_active_ctx(current_ctx_idx);
+    pub(crate) fn evaluate(self, state: &mut EvalState) -> EvalResult {
+        // Each evaluation of the block computes its bindings afresh
+        let env = state.new_env(self.ctx_idx, state.env().clone(), []);
 
-        value
+        // Evaluate the expression in the environment of this block
+        state.with_env(env, |state| self.return_expr.evaluate(state))
+    }
+
+    /// Returns the bound expressions and the return expression, in source order.
+    pub(crate) fn children<'a>(&'a self, ctx_state: &'a CtxState) -> Vec<&'a Expr> {
+        ctx_state[self.ctx_idx]
+            .values()
+            .filter_map(|binding| match binding {
+                Binding::Expr(expr) => Some(expr),
+                Binding::Cached(_) => None,
+            })
+            .chain([self.return_expr.as_ref()])
+            .collect()
     }
 
     pub(crate) fn format<W: std::fmt::Write>(
         &self,
+        span: Span,
         writer: &mut W,
         state: FmtState,
     ) -> std::fmt::Result {
@@ -124,14 +166,27 @@ impl Block {
             writeln!(writer)?;
         }
 
+        // The return expression is attached to comments after the bindings
         let ctx = &state[self.ctx_idx];
-        ctx.format(writer, state.indented())?;
+        let spans = ctx
+            .values()
+            .zip(&self.names)
+            .map(|(binding, name)| name.to(binding.span()))
+            .chain([self.return_expr.span()])
+            .collect::<Vec<_>>();
+        let comments = Attachments::new(state, span, &spans);
+
+        ctx.format(writer, state.indented(), &self.names, &comments)?;
 
         if pretty {
             write_indent(writer, state.indented().indent_level())?;
         }
 
+        let return_comments = &comments[ctx.len()];
+        return_comments.format_leading(writer, state.indented())?;
         self.return_expr.format(writer, state.indented())?;
+        return_comments.format_trailing(writer, state.indented())?;
+        comments.format_dangling(writer, state.indented())?;
 
         if pretty {
             writeln!(writer)?;
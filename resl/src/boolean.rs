@@ -6,31 +6,35 @@ use winnow::{
 
 use crate::{
     Expr, StatefulInput,
+    expr::ExprKind,
     macros::{exp_str, label},
+    utils::spanned,
 };
 
 pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-    alt((
+    spanned(
         alt((
-            "true",
-            // Fail if "true" is not correctly cased
-            preceded(
-                peek(Caseless("true")),
-                cut_err(fail).context(exp_str!("true")),
-            ),
+            alt((
+                "true",
+                // Fail if "true" is not correctly cased
+                preceded(
+                    peek(Caseless("true")),
+                    cut_err(fail).context(exp_str!("true")),
+                ),
+            ))
+            .value(true),
+            alt((
+                "false",
+                // Fail if "false" is not correctly cased
+                preceded(
+                    peek(Caseless("false")),
+                    cut_err(fail).context(exp_str!("false")),
+                ),
+            ))
+            .value(false),
         ))
-        .value(true),
-        alt((
-            "false",
-            // Fail if "false" is not correctly cased
-            preceded(
-                peek(Caseless("false")),
-                cut_err(fail).context(exp_str!("false")),
-            ),
-        ))
-        .value(false),
-    ))
-    .context(label!("boolean"))
-    .map(Expr::Bool)
+        .context(label!("boolean"))
+        .map(ExprKind::Bool),
+    )
     .parse_next(input)
 }
@@ -0,0 +1,347 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use winnow::{
+    ModalResult, Parser,
+    ascii::{multispace0, till_line_ending},
+    combinator::{alt, cut_err, opt, preceded, terminated},
+    token::take_until,
+};
+
+use crate::{
+    StatefulInput,
+    macros::{exp_str, label},
+    span::Span,
+    state::FmtState,
+    utils::{located, write_indent},
+};
+
+/// Comments of a source text, by the offset they start at.
+pub(crate) type Comments = BTreeMap<usize, Comment>;
+
+/// Offsets of the comments left to write while formatting a source text.
+pub(crate) type Pending = RefCell<BTreeSet<usize>>;
+
+/// A `//` line comment or `/* */` block comment.
+#[derive(Debug, Clone)]
+pub(crate) struct Comment {
+    /// Text between the delimiters
+    text: String,
+    /// Whether this is a block comment
+    block: bool,
+    /// Whether a line break separates the comment from the preceding token
+    own_line: bool,
+    /// Offset the comment ends at
+    end: usize,
+}
+
+impl Comment {
+    /// Writes the comment.
+    ///
+    /// Line comments are kept when pretty-printing, unless the comment is written
+    /// `inline` among other tokens. They are otherwise written as block comments,
+    /// or on a line of their own if their text would end a block comment early.
+    fn format<W: std::fmt::Write>(
+        &self,
+        writer: &mut W,
+        state: FmtState,
+        inline: bool,
+    ) -> std::fmt::Result {
+        if self.block {
+            write!(writer, "/*{}*/", self.text)
+        } else if state.pretty() && !inline {
+            write!(writer, "//{}", self.text)
+        } else if !self.text.contains("*/") {
+            write!(writer, "/*{} */", self.text)
+        } else {
+            writeln!(writer, "//{}", self.text)?;
+            if state.pretty() {
+                write_indent(writer, state.indent_level())?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Skips whitespace and comments, recording the comments in the context state.
+pub(crate) fn skip(input: &mut StatefulInput) -> ModalResult<()> {
+    let mut own_line = false;
+
+    loop {
+        own_line |= multispace0.parse_next(input)?.contains('\n');
+
+        let Some(comment) = opt(located(alt((
+            preceded("//", till_line_ending).map(|text: &str| (text.trim_end(), false)),
+            preceded(
+                "/*",
+                cut_err(terminated(take_until(0.., "*/"), "*/"))
+                    .context(label!("comment"))
+                    .context(exp_str!("*/")),
+            )
+            .map(|text: &str| (text, true)),
+        ))))
+        .parse_next(input)?
+        else {
+            return Ok(());
+        };
+
+        let (text, block) = comment.node;
+
+        input.state.record_comment(
+            comment.span.start(),
+            Comment {
+                text: text.to_string(),
+                block,
+                own_line,
+                end: comment.span.end(),
+            },
+        );
+    }
+}
+
+/// Comments attached to a binding, list item or map entry.
+#[derive(Debug, Default)]
+pub(crate) struct Attached<'a> {
+    /// Comments before the item
+    leading: Vec<&'a Comment>,
+    /// Comments after the item on the same line
+    trailing: Vec<&'a Comment>,
+}
+
+impl Attached<'_> {
+    /// Writes the comments before the item, followed by the item's indentation.
+    pub(crate) fn format_leading<W: std::fmt::Write>(
+        &self,
+        writer: &mut W,
+        state: FmtState,
+    ) -> std::fmt::Result {
+        for comment in &self.leading {
+            comment.format(writer, state, false)?;
+            if state.pretty() {
+                writeln!(writer)?;
+                write_indent(writer, state.indent_level())?;
+            } else {
+                write!(writer, " ")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether there are comments after the item.
+    pub(crate) fn has_trailing(&self) -> bool {
+        !self.trailing.is_empty()
+    }
+
+    /// Writes the comments after the item.
+    pub(crate) fn format_trailing<W: std::fmt::Write>(
+        &self,
+        writer: &mut W,
+        state: FmtState,
+    ) -> std::fmt::Result {
+        for comment in &self.trailing {
+            write!(writer, " ")?;
+            comment.format(writer, state, false)?;
+        }
+        Ok(())
+    }
+}
+
+/// Comments of a block, list or map, attached to its items.
+#[derive(Debug)]
+pub(crate) struct Attachments<'a> {
+    items: Vec<Attached<'a>>,
+    /// Comments after the last item on their own line
+    dangling: Vec<&'a Comment>,
+}
+
+impl<'a> Attachments<'a> {
+    /// Attaches the comments within `span` to the items spanning `items`.
+    ///
+    /// A comment on the same line after an item is attached to that item, any other
+    /// comment to the item after it. Comments nested inside an item, directly after
+    /// an item that is not the last, or on the same line directly before an item are
+    /// left to the item itself. Items may be given in any order.
+    pub(crate) fn new(state: FmtState<'a>, span: Span, items: &[Span]) -> Self {
+        let comments = state.ctx_state().comments();
+        let mut pending = state.pending().borrow_mut();
+        let mut attachments = Self {
+            items: items.iter().map(|_| Attached::default()).collect(),
+            dangling: Vec::new(),
+        };
+
+        let mut order = (0..items.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&idx| items[idx].start());
+
+        let mut cursor = span.start();
+        let mut previous: Option<usize> = None;
+
+        for idx in order {
+            let start = items[idx].start().max(cursor);
+            for (offset, comment) in comments.range(cursor..start) {
+                match previous {
+                    // Written before the separator following the item
+                    Some(previous) if token_before(state, *offset) == items[previous].end() => {
+                        continue;
+                    }
+                    // Written before the item on its line
+                    _ if !state.source()[comment.end..start].contains('\n') => continue,
+                    Some(previous) if !comment.own_line => {
+                        attachments.items[previous].trailing.push(comment)
+                    }
+                    _ => attachments.items[idx].leading.push(comment),
+                }
+                pending.remove(offset);
+            }
+            cursor = items[idx].end().max(cursor);
+            previous = Some(idx);
+        }
+
+        for (offset, comment) in comments.range(cursor..span.end().max(cursor)) {
+            pending.remove(offset);
+            match previous {
+                Some(previous) if !comment.own_line => {
+                    attachments.items[previous].trailing.push(comment)
+                }
+                _ => attachments.dangling.push(comment),
+            }
+        }
+
+        attachments
+    }
+
+    /// Returns whether there are comments after the last item.
+    pub(crate) fn has_dangling(&self) -> bool {
+        !self.dangling.is_empty()
+    }
+
+    /// Writes the comments after the last item, each on its own line when pretty-printing.
+    pub(crate) fn format_dangling<W: std::fmt::Write>(
+        &self,
+        writer: &mut W,
+        state: FmtState,
+    ) -> std::fmt::Result {
+        for comment in &self.dangling {
+            if state.pretty() {
+                writeln!(writer)?;
+                write_indent(writer, state.indent_level())?;
+            } else {
+                write!(writer, " ")?;
+            }
+            comment.format(writer, state, false)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> std::ops::Index<usize> for Attachments<'a> {
+    type Output = Attached<'a>;
+
+    fn index(&self, idx: usize) -> &Self::Output {
+        &self.items[idx]
+    }
+}
+
+/// Writes a node spanning `span` along with the comments around it.
+///
+/// Comments no block, list or map attaches to an item are written where they are
+/// found: those directly after the node follow it, and the rest precede the node
+/// after them. Comments are only ever moved when nothing else is left to write
+/// them, such as those after the last argument of a call, and are then written
+/// after the node containing them.
+pub(crate) fn format_node<W: std::fmt::Write>(
+    writer: &mut W,
+    state: FmtState,
+    span: Span,
+    format: impl FnOnce(&mut W) -> std::fmt::Result,
+) -> std::fmt::Result {
+    // Comments before the node are left over from earlier nodes, if not directly before it
+    for comment in take_pending(state, span.start(), |_| true) {
+        comment.format(writer, state, true)?;
+        write!(writer, " ")?;
+    }
+
+    format(writer)?;
+
+    // Comments within the node are only left over if nothing in it could write them
+    let directly_after = |offset| token_before(state, offset) == span.end();
+    for comment in take_pending(state, span.end(), |_| true)
+        .into_iter()
+        .chain(take_pending(state, usize::MAX, directly_after))
+    {
+        write!(writer, " ")?;
+        comment.format(writer, state, true)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the comments left before `end`, where a node ends whose parts could not
+/// hold them, such as the arguments of a call. A space precedes the first comment
+/// if `spaced`.
+pub(crate) fn format_within<W: std::fmt::Write>(
+    writer: &mut W,
+    state: FmtState,
+    end: usize,
+    spaced: bool,
+) -> std::fmt::Result {
+    for (idx, comment) in take_pending(state, end, |_| true).into_iter().enumerate() {
+        if spaced || idx > 0 {
+            write!(writer, " ")?;
+        }
+        comment.format(writer, state, true)?;
+    }
+    Ok(())
+}
+
+/// Drops the pending comments before `end`, within a node written as in the source.
+pub(crate) fn skip_pending(state: FmtState, end: usize) {
+    take_pending(state, end, |_| true);
+}
+
+/// Removes the pending comments starting before `until` from the pending ones, as
+/// long as they pass `filter`, and returns them in order.
+fn take_pending<'a>(
+    state: FmtState<'a>,
+    until: usize,
+    filter: impl Fn(usize) -> bool,
+) -> Vec<&'a Comment> {
+    let mut pending = state.pending().borrow_mut();
+    let offsets = pending
+        .range(..until)
+        .copied()
+        .take_while(|&offset| filter(offset))
+        .collect::<Vec<_>>();
+
+    offsets
+        .into_iter()
+        .map(|offset| {
+            pending.remove(&offset);
+            &state.ctx_state().comments()[&offset]
+        })
+        .collect()
+}
+
+/// Returns the start of the token after `offset`, skipping whitespace and comments.
+pub(crate) fn token_after(state: FmtState, mut offset: usize) -> usize {
+    loop {
+        let source = state.source();
+        offset = source.len() - source[offset..].trim_start().len();
+        match state.comments().get(&offset) {
+            Some(comment) => offset = comment.end,
+            None => return offset,
+        }
+    }
+}
+
+/// Returns the end of the token before `offset`, skipping whitespace and comments.
+pub(crate) fn token_before(state: FmtState, mut offset: usize) -> usize {
+    loop {
+        offset = state.source()[..offset].trim_end().len();
+        match state.comments().range(..offset).next_back() {
+            Some((&start, comment)) if comment.end >= offset => offset = start,
+            _ => return offset,
+        }
+    }
+}
@@ -1,27 +1,26 @@
 use crate::{
     binding::Binding,
-    expr::Expr,
+    comment::{self, Attachments},
+    expr::{Expr, ExprKind},
     function::builtin::BUILTIN_FUNCTIONS,
     ident::Ident,
+    span::Span,
     state::{FmtState, Interner},
     utils::write_indent,
-    value::Value,
 };
 
 type Bindings = indexmap::IndexMap<Ident, Binding>;
-type LookupStack = std::collections::HashSet<Ident>;
 
 /// Represents a variable binding context with optional parent scope.
 ///
-/// Contexts manage variable bindings within a specific scope and provide
-/// hierarchical variable resolution through parent contexts. Each context
-/// maintains its own set of variable bindings and tracks circular reference
-/// detection during variable lookups.
+/// Contexts hold the variable bindings of a specific scope as written, and are
+/// nested within the context of their enclosing scope. Values computed while
+/// evaluating a scope are kept in an [`Env`](crate::env::Env) instead, so that a
+/// context can be evaluated any number of times.
 #[derive(Debug, Default, Clone)]
 pub struct Context {
     parent_ctx_idx: Option<usize>,
     bindings: Bindings,
-    lookup_stack: LookupStack,
 }
 
 impl Context {
@@ -30,14 +29,13 @@ impl Context {
         let bindings = Bindings::from_iter(BUILTIN_FUNCTIONS.into_iter().map(|(name, func)| {
             (
                 Ident::using_interner(name, interner),
-                Binding::Expr(Expr::Fn(func)),
+                Binding::Expr(Expr::new(ExprKind::Fn(func), Default::default())),
             )
         }));
 
         Self {
             parent_ctx_idx: None,
             bindings,
-            lookup_stack: LookupStack::new(),
         }
     }
 
@@ -55,57 +53,26 @@ impl Context {
         Self {
             parent_ctx_idx,
             bindings: Bindings::from_iter(iter.into_iter().map(|(k, b)| (k, b.into()))),
-            lookup_stack: LookupStack::new(),
         }
     }
 
-    /// Assigns expressions to existing bindings from an iterator.
-    pub(crate) fn assign_from_iter<T, B>(&mut self, iter: T)
-    where
-        T: IntoIterator<Item = B>,
-        B: Into<Binding>,
-    {
-        let mut iter = iter.into_iter();
-        for (_, binding) in &mut self.bindings {
-            // SAFETY: We ensure that the number of expressions matches the number of bindings
-            let b = unsafe { iter.next().unwrap_unchecked() };
-
-            *binding = b.into();
-        }
-    }
-
-    /// Resets all bindings to default expressions.
-    pub(crate) fn reassign_default_expr(&mut self) {
-        self.iter_mut()
-            .for_each(|(_, binding)| *binding = Binding::default());
-    }
-
-    /// Initiates a variable lookup, returns true if this creates a circular reference.
-    pub(crate) fn initiate_lookup(&mut self, ident: &Ident) -> bool {
-        self.lookup_stack.insert(ident.to_owned())
-    }
-
-    /// Concludes a variable lookup, removing it from the lookup stack.
-    pub(crate) fn conclude_lookup(&mut self, ident: &Ident) {
-        self.lookup_stack.remove(ident);
-    }
-
-    /// Caches a computed value for an identifier.
-    pub(crate) fn cache(&mut self, ident: &Ident, value: Value) {
-        self[ident] = Binding::Cached(value)
-    }
-
     /// Formats the context's bindings to a writer with proper indentation.
+    ///
+    /// Comments are attached to the bindings in order, and written around the names
+    /// spanning `names`.
     pub(crate) fn format<W: std::fmt::Write>(
         &self,
         writer: &mut W,
         state: FmtState,
+        names: &[Span],
+        comments: &Attachments,
     ) -> std::fmt::Result {
-        for (name, expr) in &self.bindings {
+        for (idx, ((name, expr), span)) in self.bindings.iter().zip(names).enumerate() {
             if state.pretty() {
                 write_indent(writer, state.indent_level())?;
             }
-            name.format(writer, state)?;
+            comments[idx].format_leading(writer, state)?;
+            comment::format_node(writer, state, *span, |writer| name.format(writer, state))?;
             if state.pretty() {
                 write!(writer, " ")?;
             }
@@ -115,8 +82,12 @@ impl Context {
             }
             expr.format(writer, state)?;
             write!(writer, ";")?;
+            comments[idx].format_trailing(writer, state)?;
             if state.pretty() {
                 writeln!(writer)?;
+            } else if comments[idx].has_trailing() {
+                // Keeps the comment apart from the next name
+                write!(writer, " ")?;
             }
         }
         Ok(())
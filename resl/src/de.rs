@@ -0,0 +1,409 @@
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+
+use crate::{
+    error::{DeserializeError, Error},
+    evaluate_strict,
+    value::{Value, ValueList, ValueMap},
+};
+
+/// Deserializes an instance of `T` from a RESL expression.
+///
+/// The input is evaluated strictly, so runtime errors are reported instead of
+/// being deserialized as null values.
+///
+/// # Arguments
+///
+/// * `input` - A string slice containing the RESL expression to evaluate
+///
+/// # Returns
+///
+/// * `Ok(T)` - The deserialized value
+/// * `Err(Error)` - If parsing, evaluation or deserialization fails
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+///     tls: Option<bool>,
+/// }
+///
+/// let server: Server = resl::from_str(
+///     r#"{
+///         base = 8000;
+///         ["host": "localhost", "port": base + 80, "tls": null]
+///     }"#,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(server.host, "localhost");
+/// assert_eq!(server.port, 8080);
+/// assert_eq!(server.tls, None);
+/// ```
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T, Error> {
+    let value = evaluate_strict(input)?;
+
+    Ok(from_value(value)?)
+}
+
+/// Deserializes an instance of `T` from an evaluated RESL value.
+///
+/// Maps deserialize into structs and maps, lists into sequences and tuples,
+/// strings into unit enum variants and null into `None` or `()`. A map with a
+/// single entry deserializes into the enum variant named by its key.
+///
+/// # Arguments
+///
+/// * `value` - The value to deserialize
+///
+/// # Returns
+///
+/// * `Ok(T)` - The deserialized value
+/// * `Err(DeserializeError)` - If the value does not match the shape of `T`
+///
+/// # Examples
+///
+/// ```
+/// use resl::{Value, from_value};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// #[serde(rename_all = "lowercase")]
+/// enum Mode {
+///     Debug,
+///     Release,
+/// }
+///
+/// let value = resl::evaluate("[\"debug\", \"release\"]").unwrap();
+/// let modes: Vec<Mode> = from_value(value).unwrap();
+/// assert_eq!(modes, [Mode::Debug, Mode::Release]);
+///
+/// let value = resl::evaluate("[\"ports\": [80, \"443\"]]").unwrap();
+/// let err = from_value::<std::collections::HashMap<String, Vec<u16>>>(value).unwrap_err();
+/// assert_eq!(err.path, "ports[1]");
+/// ```
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, DeserializeError> {
+    T::deserialize(ValueDeserializer::new(value, String::new()))
+}
+
+/// Deserializer over an owned value that tracks the path to the value.
+struct ValueDeserializer {
+    value: Value,
+    path: String,
+}
+
+impl ValueDeserializer {
+    fn new(value: Value, path: String) -> Self {
+        Self { value, path }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = DeserializeError;
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct identifier
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let path = self.path;
+
+        match self.value {
+            Value::Null => visitor.visit_unit(),
+            Value::Boolean(b) => visitor.visit_bool(b),
+            Value::Integer(i) => visitor.visit_i64(i),
+            Value::Float(f) => visitor.visit_f64(f),
+            Value::String(s) => visitor.visit_string(s),
+            Value::List(list) => visitor.visit_seq(SeqDeserializer::new(list, path.clone())),
+            Value::Map(map) => visitor.visit_map(MapDeserializer::new(map, path.clone())),
+            value @ Value::Function(_) => {
+                Err(de::Error::invalid_type(unexpected(&value), &visitor))
+            }
+        }
+        .map_err(|err| err.at(&path))
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            // `[]` is both an empty list and an empty map, and evaluates to a list
+            Value::List(list) if list.is_empty() => visitor
+                .visit_map(MapDeserializer::new(ValueMap::new(), self.path.clone()))
+                .map_err(|err| err.at(&self.path)),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let path = self.path;
+
+        match self.value {
+            // Unit variants are written as plain strings
+            Value::String(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                value: None,
+                path: path.clone(),
+            }),
+            // Other variants are written as a map with a single entry
+            Value::Map(map) if map.len() == 1 => {
+                let (variant, value) = map.into_iter().next().expect("Map has a single entry");
+                let value_path = join_key(&path, &variant);
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value: Some(value),
+                    path: value_path,
+                })
+            }
+            value => Err(de::Error::invalid_type(
+                unexpected(&value),
+                &"a string or a map with a single entry",
+            )),
+        }
+        .map_err(|err| err.at(&path))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+}
+
+/// Sequence access over the elements of a list.
+struct SeqDeserializer {
+    iter: std::iter::Enumerate<std::vec::IntoIter<Value>>,
+    path: String,
+}
+
+impl SeqDeserializer {
+    fn new(list: ValueList, path: String) -> Self {
+        Self {
+            iter: list.into_iter().enumerate(),
+            path,
+        }
+    }
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((index, value)) => {
+                let path = format!("{}[{index}]", self.path);
+                seed.deserialize(ValueDeserializer::new(value, path))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Map access over the entries of a map.
+struct MapDeserializer {
+    iter: <ValueMap as IntoIterator>::IntoIter,
+    value: Option<(String, Value)>,
+    path: String,
+}
+
+impl MapDeserializer {
+    fn new(map: ValueMap, path: String) -> Self {
+        Self {
+            iter: map.into_iter(),
+            value: None,
+            path,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                let key_value = seed.deserialize(key.as_str().into_deserializer())?;
+                self.value = Some((key, value));
+                Ok(Some(key_value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value is missing"))?;
+
+        seed.deserialize(ValueDeserializer::new(value, join_key(&self.path, &key)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Enum access over a variant name and its optional content.
+struct EnumDeserializer {
+    variant: String,
+    value: Option<Value>,
+    path: String,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = DeserializeError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+
+        Ok((
+            variant,
+            VariantDeserializer {
+                value: self.value,
+                path: self.path,
+            },
+        ))
+    }
+}
+
+/// Variant access over the optional content of an enum variant.
+struct VariantDeserializer {
+    value: Option<Value>,
+    path: String,
+}
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+    type Error = DeserializeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self.value {
+            None | Some(Value::Null) => Ok(()),
+            Some(value) => Err(de::Error::invalid_type(unexpected(&value), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        match self.value {
+            Some(value) => seed.deserialize(ValueDeserializer::new(value, self.path)),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Some(value) => {
+                de::Deserializer::deserialize_seq(ValueDeserializer::new(value, self.path), visitor)
+            }
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Some(value) => {
+                de::Deserializer::deserialize_map(ValueDeserializer::new(value, self.path), visitor)
+            }
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+/// Describes a value for `invalid_type` errors.
+fn unexpected(value: &Value) -> de::Unexpected<'_> {
+    match value {
+        Value::Null => de::Unexpected::Unit,
+        Value::Boolean(b) => de::Unexpected::Bool(*b),
+        Value::Integer(i) => de::Unexpected::Signed(*i),
+        Value::Float(f) => de::Unexpected::Float(*f),
+        Value::String(s) => de::Unexpected::Str(s),
+        Value::List(_) => de::Unexpected::Seq,
+        Value::Map(_) => de::Unexpected::Map,
+        Value::Function(_) => de::Unexpected::Other("function"),
+    }
+}
+
+/// Appends a map key to a path, quoting keys that are not plain identifiers.
+fn join_key(path: &str, key: &str) -> String {
+    let is_plain = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || ['_', '-', '$'].contains(&c));
+
+    match (path.is_empty(), is_plain) {
+        (true, true) => key.to_string(),
+        (false, true) => format!("{path}.{key}"),
+        (_, false) => format!("{path}[{key:?}]"),
+    }
+}
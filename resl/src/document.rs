@@ -0,0 +1,88 @@
+use crate::{error::ParseError, expr::Expr, span::Span, state::CtxState};
+
+/// RESL document parsed without being evaluated.
+///
+/// A document gives tools such as editors and linters the position of every part
+/// of the source text: [`Document::exprs`] lists every expression along with its
+/// [`Span`], and [`Document::names`] lists the names the document declares.
+///
+/// # Examples
+///
+/// ```
+/// use resl::{NameKind, parse};
+///
+/// let input = "{port = 80; [\"url\": \"http://host:${port}\"]}";
+/// let document = parse(input).unwrap();
+///
+/// let names = document
+///     .names()
+///     .map(|name| (name.kind, &input[name.span.start()..name.span.end()]))
+///     .collect::<Vec<_>>();
+/// assert_eq!(names, [(NameKind::Binding, "port"), (NameKind::Key, "\"url\"")]);
+///
+/// // The literal `80` is the second expression, after the whole block
+/// let literal = document.exprs()[1];
+/// assert_eq!(&input[literal.span().start()..literal.span().end()], "80");
+/// ```
+#[derive(Debug)]
+pub struct Document {
+    expr: Expr,
+    ctx_state: CtxState,
+}
+
+/// Name declared in a document, along with its span.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name {
+    /// The name, or the value of the key for map keys
+    pub text: String,
+    /// How the name is declared
+    pub kind: NameKind,
+    /// Byte range of the name in the source text, including the quotes of keys
+    pub span: Span,
+}
+
+/// How a name is declared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NameKind {
+    /// Name bound in a block, such as `port` in `{port = 80; port}`.
+    Binding,
+    /// Parameter of a function, name of a for-each pattern, or name bound by a
+    /// pattern of a match expression.
+    Parameter,
+    /// Literal key of a map or of a map pattern.
+    Key,
+}
+
+impl Document {
+    /// Parses a document, failing if the input is not a single valid expression.
+    pub(crate) fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut ctx_state = CtxState::new();
+        let expr = Expr::parse_all(input, &mut ctx_state)?;
+
+        Ok(Self { expr, ctx_state })
+    }
+
+    /// Returns the expression the document consists of.
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Returns every expression of the document, each before the expressions it
+    /// contains and in source order otherwise.
+    pub fn exprs(&self) -> Vec<&Expr> {
+        let mut exprs = Vec::new();
+        let mut stack = vec![&self.expr];
+
+        while let Some(expr) = stack.pop() {
+            exprs.push(expr);
+            stack.extend(expr.children(&self.ctx_state).into_iter().rev());
+        }
+
+        exprs
+    }
+
+    /// Returns the names declared in the document, in source order.
+    pub fn names(&self) -> impl Iterator<Item = &Name> {
+        self.ctx_state.names()
+    }
+}
@@ -0,0 +1,304 @@
+use std::{collections::HashSet, sync::Arc};
+
+use indexmap::IndexMap;
+
+use crate::{
+    binding::Binding,
+    env::ClosureCache,
+    error::{Error, RuntimeErrorKind},
+    expr::{Expr, ExprKind},
+    function::{Fn, native::Native},
+    module::{DenyResolver, Modules, Resolver},
+    state::{CtxState, EvalState},
+    value::Value,
+};
+
+/// Configurable evaluator for RESL expressions.
+///
+/// An `Engine` lets the host program extend the root context before evaluation.
+/// Functions registered with [`Engine::register_fn`] can be called from RESL like
+/// the built-in functions, and may capture state from the host. Variables defined
+/// with [`Engine::define_var`] can be referenced as free identifiers, so the same
+/// document can be evaluated with different inputs. The free functions
+/// [`evaluate`](crate::evaluate) and [`evaluate_strict`](crate::evaluate_strict)
+/// behave like a default engine.
+///
+/// Modules loaded with `import` are located by the engine's [`Resolver`]. Imports
+/// are refused unless a resolver is configured with [`Engine::resolver`], so
+/// untrusted documents cannot read files from the host.
+///
+/// The `env` function can only read environment variables allowed with
+/// [`Engine::allow_env`], so untrusted documents cannot read secrets from the host.
+///
+/// # Examples
+///
+/// ```
+/// use resl::{Engine, RuntimeErrorKind, Value};
+///
+/// let prefix = String::from("app");
+///
+/// let engine = Engine::new().register_fn("qualify", move |args| match args {
+///     [Value::String(name)] => Ok(Value::String(format!("{prefix}-{name}"))),
+///     _ => Err(RuntimeErrorKind::Custom("expected a single string".to_string())),
+/// });
+///
+/// let result = engine.evaluate("qualify(\"web\")").unwrap();
+/// assert_eq!(result, Value::String("app-web".to_string()));
+/// ```
+#[derive(Clone)]
+pub struct Engine {
+    bindings: IndexMap<String, Binding>,
+    resolver: Arc<dyn Resolver>,
+    allowed_env: HashSet<String>,
+    strict: bool,
+}
+
+impl Engine {
+    /// Creates an engine with only the built-in functions, evaluating leniently.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a native function callable from RESL as `name(...)`.
+    ///
+    /// The function receives the evaluated arguments and returns either a value or
+    /// the kind of failure to report at the call site. Registering a name that is
+    /// already taken replaces the previous function or variable, including built-in
+    /// functions.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The identifier the function is bound to
+    /// * `func` - The function to call with the evaluated arguments
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use resl::{Engine, RuntimeErrorKind, Value};
+    ///
+    /// let engine = Engine::new().register_fn("max", |args| {
+    ///     args.iter()
+    ///         .filter_map(|arg| match arg {
+    ///             Value::Integer(i) => Some(*i),
+    ///             _ => None,
+    ///         })
+    ///         .max()
+    ///         .map(Value::Integer)
+    ///         .ok_or(RuntimeErrorKind::Custom("no integers given".to_string()))
+    /// });
+    ///
+    /// assert_eq!(engine.evaluate("max(3, 9, 4)").unwrap(), Value::Integer(9));
+    /// ```
+    pub fn register_fn<F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: std::ops::Fn(&[Value]) -> Result<Value, RuntimeErrorKind> + Send + Sync + 'static,
+    {
+        let func = Expr::new(
+            ExprKind::Fn(Fn::Native(Native::new(func))),
+            Default::default(),
+        );
+        self.bindings.insert(name.into(), Binding::Expr(func));
+        self
+    }
+
+    /// Defines a variable that RESL can reference as `name`.
+    ///
+    /// Variables live in the root context, so bindings declared by the document
+    /// shadow them. Defining a name that is already taken replaces the previous
+    /// variable or function, including built-in functions.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The identifier the value is bound to
+    /// * `value` - The value of the variable
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use resl::{Engine, Value};
+    ///
+    /// let config = r#"? production : ["replicas": 3] | ["replicas": 1]"#;
+    ///
+    /// let prod = Engine::new().define_var("production", Value::Boolean(true));
+    /// let dev = Engine::new().define_var("production", Value::Boolean(false));
+    ///
+    /// assert_eq!(prod.evaluate(config).unwrap().to_string(), r#"["replicas": 3]"#);
+    /// assert_eq!(dev.evaluate(config).unwrap().to_string(), r#"["replicas": 1]"#);
+    /// ```
+    pub fn define_var(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.bindings.insert(name.into(), Binding::Cached(value));
+        self
+    }
+
+    /// Defines a variable for every name and value pair.
+    ///
+    /// This is equivalent to calling [`Engine::define_var`] for each pair in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use resl::{Engine, Value};
+    ///
+    /// let engine = Engine::new().define_vars([
+    ///     ("region", Value::String("eu-west-1".to_string())),
+    ///     ("zone", Value::String("b".to_string())),
+    /// ]);
+    ///
+    /// let result = engine.evaluate("concat(region, zone)").unwrap();
+    /// assert_eq!(result, Value::String("eu-west-1b".to_string()));
+    /// ```
+    pub fn define_vars<I, K>(self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, Value)>,
+        K: Into<String>,
+    {
+        vars.into_iter()
+            .fold(self, |engine, (name, value)| engine.define_var(name, value))
+    }
+
+    /// Allows RESL to read the environment variable `name` with `env`.
+    ///
+    /// No environment variables can be read unless allowed. Reading any other
+    /// variable fails as if it were a runtime error, even if a default is given.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use resl::{Engine, Value};
+    ///
+    /// let config = r#"env("CARGO_PKG_NAME", "unknown")"#;
+    ///
+    /// let allowed = Engine::new().allow_env("CARGO_PKG_NAME");
+    /// assert_eq!(allowed.evaluate(config).unwrap(), Value::String("resl".to_string()));
+    ///
+    /// // Without access the lenient engine evaluates the call to null
+    /// assert_eq!(Engine::new().evaluate(config).unwrap(), Value::Null);
+    /// ```
+    pub fn allow_env(mut self, name: impl Into<String>) -> Self {
+        self.allowed_env.insert(name.into());
+        self
+    }
+
+    /// Allows RESL to read every environment variable in `names` with `env`.
+    ///
+    /// This is equivalent to calling [`Engine::allow_env`] for each name.
+    pub fn allow_envs<I, K>(self, names: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        names
+            .into_iter()
+            .fold(self, |engine, name| engine.allow_env(name))
+    }
+
+    /// Sets whether runtime errors abort evaluation.
+    ///
+    /// Lenient engines evaluate failing expressions to `null`, like
+    /// [`evaluate`](crate::evaluate). Strict engines report the first runtime error,
+    /// like [`evaluate_strict`](crate::evaluate_strict).
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets the resolver used to locate and load modules referenced by `import`.
+    ///
+    /// Without one, every import is refused, as with [`DenyResolver`](crate::DenyResolver).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use resl::{Engine, MemoryResolver, Value};
+    ///
+    /// let engine = Engine::new().resolver(
+    ///     MemoryResolver::new().with_module("ports.resl", "[\"http\": 80, \"https\": 443]"),
+    /// );
+    ///
+    /// let result = engine
+    ///     .evaluate("{ports = import(\"ports.resl\"); ports[\"https\"]}")
+    ///     .unwrap();
+    /// assert_eq!(result, Value::Integer(443));
+    /// ```
+    pub fn resolver(mut self, resolver: impl Resolver + 'static) -> Self {
+        self.resolver = Arc::new(resolver);
+        self
+    }
+
+    /// Evaluates a RESL expression from a string and returns the computed value.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A string slice containing the RESL expression to evaluate
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Value)` - The evaluated result as a RESL value
+    /// * `Err(Error::Parse)` - If parsing fails
+    /// * `Err(Error::Runtime)` - If evaluation fails in strict mode
+    pub fn evaluate(&self, input: &str) -> Result<Value, Error> {
+        let mut ctx_state = self.ctx_state();
+
+        let expression = Expr::parse_all(input, &mut ctx_state)?;
+
+        // Cycles between closures and their environments are broken once the value
+        // is computed, as the closure cache is dropped
+        let value = expression
+            .evaluate(&mut EvalState::new(
+                Arc::new(ctx_state),
+                self,
+                &mut Modules::default(),
+                &mut ClosureCache::default(),
+            ))
+            .map_err(|err| err.locate(input))?;
+
+        Ok(value)
+    }
+
+    /// Returns whether runtime errors abort evaluation.
+    pub(crate) fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Returns whether `env` may read the environment variable `name`.
+    pub(crate) fn is_env_allowed(&self, name: &str) -> bool {
+        self.allowed_env.contains(name)
+    }
+
+    /// Gets the resolver used to locate modules.
+    pub(crate) fn module_resolver(&self) -> &dyn Resolver {
+        self.resolver.as_ref()
+    }
+
+    /// Creates the context state with the registered bindings in the root context.
+    pub(crate) fn ctx_state(&self) -> CtxState {
+        let mut ctx_state = CtxState::new();
+
+        for (name, binding) in &self.bindings {
+            ctx_state.bind_root(name, binding.clone());
+        }
+
+        ctx_state
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self {
+            bindings: IndexMap::new(),
+            resolver: Arc::new(DenyResolver),
+            allowed_env: HashSet::new(),
+            strict: false,
+        }
+    }
+}
+
+impl std::fmt::Debug for Engine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Engine")
+            .field("bindings", &self.bindings)
+            .field("allowed_env", &self.allowed_env)
+            .field("strict", &self.strict)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard, Weak},
+};
+
+use crate::{ident::Ident, value::Value};
+
+/// Bindings of a single evaluation of a context.
+///
+/// A context describes the bindings of a block, function or for-each as written.
+/// Every time one is evaluated, a new environment holds the values of its
+/// parameters and of the bindings computed so far. Repeated, nested and recursive
+/// evaluations therefore never observe each other's values. The parent is the
+/// environment the context was written in, so lookups follow lexical scope.
+///
+/// Closures keep the environment they were declared in, so environments are shared
+/// and may outlive the evaluation as part of a value.
+#[derive(Debug)]
+pub(crate) struct Env {
+    ctx_idx: usize,
+    parent: Option<Arc<Env>>,
+    values: Mutex<HashMap<Ident, Value>>,
+    lookup_stack: Mutex<HashSet<Ident>>,
+}
+
+impl Env {
+    /// Creates the environment of the root context.
+    pub(crate) fn root() -> Arc<Self> {
+        Self::new(0, None, [])
+    }
+
+    /// Creates an environment for the context at `ctx_idx` with the given parameter values.
+    pub(crate) fn new(
+        ctx_idx: usize,
+        parent: Option<Arc<Env>>,
+        params: impl IntoIterator<Item = (Ident, Value)>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            ctx_idx,
+            parent,
+            values: Mutex::new(params.into_iter().collect()),
+            lookup_stack: Mutex::default(),
+        })
+    }
+
+    /// Gets the index of the context this environment evaluates.
+    pub(crate) fn ctx_idx(&self) -> usize {
+        self.ctx_idx
+    }
+
+    /// Gets the environment the context was written in, if any.
+    pub(crate) fn parent(&self) -> Option<&Arc<Env>> {
+        self.parent.as_ref()
+    }
+
+    /// Gets the value of a parameter or of a binding that was already computed.
+    pub(crate) fn get(&self, ident: &Ident) -> Option<Value> {
+        lock(&self.values).get(ident).cloned()
+    }
+
+    /// Caches a computed value for an identifier.
+    pub(crate) fn cache(&self, ident: &Ident, value: Value) {
+        lock(&self.values).insert(ident.to_owned(), value);
+    }
+
+    /// Drops the cached value of a binding, which is computed again if looked up.
+    pub(crate) fn uncache(&self, ident: &Ident) {
+        lock(&self.values).remove(ident);
+    }
+
+    /// Initiates a variable lookup, returns false if this creates a circular reference.
+    pub(crate) fn initiate_lookup(&self, ident: &Ident) -> bool {
+        lock(&self.lookup_stack).insert(ident.to_owned())
+    }
+
+    /// Concludes a variable lookup, removing it from the lookup stack.
+    pub(crate) fn conclude_lookup(&self, ident: &Ident) {
+        lock(&self.lookup_stack).remove(ident);
+    }
+}
+
+/// Bindings whose cached values contain closures, recorded during a single evaluation.
+///
+/// A closure keeps the environment it was declared in alive, so caching one in an
+/// environment it can reach creates a reference cycle. Only cached bindings can refer
+/// to environments created after their own, so dropping their values once the
+/// evaluation ends breaks every cycle. Closures outliving the evaluation compute
+/// them again if they need them.
+#[derive(Debug, Default)]
+pub(crate) struct ClosureCache(Vec<(Weak<Env>, Ident)>);
+
+impl ClosureCache {
+    /// Records that `env` caches a value containing a closure for `ident`.
+    pub(crate) fn record(&mut self, env: &Arc<Env>, ident: &Ident) {
+        // Forget environments that were already dropped before growing
+        if self.0.len() == self.0.capacity() {
+            self.0.retain(|(env, _)| env.strong_count() > 0);
+        }
+        self.0.push((Arc::downgrade(env), ident.to_owned()));
+    }
+}
+
+impl Drop for ClosureCache {
+    fn drop(&mut self) {
+        for (env, ident) in self.0.drain(..) {
+            if let Some(env) = env.upgrade() {
+                env.uncache(&ident);
+            }
+        }
+    }
+}
+
+/// Locks a mutex, ignoring poisoning since single insertions and removals cannot
+/// leave the map inconsistent.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
@@ -3,7 +3,10 @@ use winnow::{
     stream::AsBStr,
 };
 
-use crate::StatefulInput;
+use crate::{StatefulInput, span::Span, value::Value};
+
+/// Result of evaluating an expression.
+pub(crate) type EvalResult<T = Value> = Result<T, RuntimeError>;
 
 /// Represents parsing errors in the RESL language.
 ///
@@ -11,8 +14,8 @@ use crate::StatefulInput;
 /// the problematic line content, and context about what was expected during parsing.
 /// It formats errors in a user-friendly way similar to modern compiler error messages.
 ///
-/// Note: This error type only covers parsing failures. RESL evaluation is infallible
-/// and does not produce runtime errors.
+/// Note: This error type only covers parsing failures. Failures during evaluation are
+/// reported as [`RuntimeError`] when using [`evaluate_strict`](crate::evaluate_strict).
 ///
 /// # Examples
 ///
@@ -47,45 +50,7 @@ pub struct ParseError {
 /// context information to provide helpful suggestions about what was expected.
 impl From<WinnowParseError<StatefulInput<'_, '_>, ContextError>> for ParseError {
     fn from(value: WinnowParseError<StatefulInput, ContextError>) -> Self {
-        let offset = value.offset();
-        let input_str = value.input().as_bstr();
-
-        let mut line_start_byte = 0;
-        let mut line_end_byte = input_str.len();
-        let mut line_number = 1;
-        let mut column = 1;
-
-        // Enumerate over the input string from starting to the offset
-        // This is to find the `line_number`, `line_start_byte`, and `column`
-        for (index, byte) in input_str[0..offset].iter().enumerate() {
-            // Check if byte represents a new line
-            if *byte == b'\n' {
-                // Set `line_start_byte` at the next index of the `\n` char
-                line_start_byte = index + 1;
-                // Increment `line_number` by 1
-                line_number += 1;
-                // Reset `column` to 1
-                column = 1;
-            } else {
-                // Increment `column` by 1
-                column += 1;
-            }
-        }
-
-        // Enumerate over the input string from the offset to the end
-        // This is to find the `line_end_byte`
-        for (index, byte) in input_str[offset..].iter().enumerate() {
-            // Check if byte represents a new line
-            if *byte == b'\n' {
-                line_end_byte = index + offset;
-                break;
-            }
-        }
-
-        // The content is always valid UTF-8 since the input is guaranteed to be valid UTF-8
-        let line_content =
-            unsafe { str::from_utf8_unchecked(&input_str[line_start_byte..line_end_byte]) }
-                .to_string();
+        let (line_number, column, line_content) = locate(value.input().as_bstr(), value.offset());
 
         let mut label = None;
         let mut expected = Vec::new();
@@ -186,3 +151,455 @@ impl std::fmt::Display for ParseError {
 }
 
 impl std::error::Error for ParseError {}
+
+/// Represents errors raised while evaluating a RESL expression.
+///
+/// Runtime errors are only produced by strict evaluation; the default evaluation mode
+/// replaces every failing expression with `null`. Each error carries the kind of failure
+/// and the span of the offending expression, along with the line it appears on.
+///
+/// # Examples
+///
+/// ```
+/// use resl::{Error, RuntimeErrorKind, evaluate_strict};
+///
+/// let result = evaluate_strict("{port = \"80\"; port * 2}");
+///
+/// match result {
+///     Err(Error::Runtime(err)) => assert_eq!(
+///         err.kind,
+///         RuntimeErrorKind::TypeMismatch {
+///             expected: "integer or float",
+///             found: "string",
+///         }
+///     ),
+///     _ => panic!("expected a runtime error"),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    /// The kind of failure that occurred
+    pub kind: RuntimeErrorKind,
+    /// The byte range of the offending expression
+    pub span: Span,
+    /// The line number where the offending expression starts (1-indexed)
+    pub line_number: usize,
+    /// The column number where the offending expression starts (1-indexed)
+    pub column: usize,
+    /// The content of the line where the offending expression starts
+    pub line_content: String,
+}
+
+/// The kinds of failures that can occur during evaluation.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RuntimeErrorKind {
+    /// An operation received a value of an unsupported type.
+    TypeMismatch {
+        /// Description of the accepted types
+        expected: &'static str,
+        /// Type of the value that was supplied
+        found: &'static str,
+    },
+    /// A function was called with the wrong number of arguments.
+    ArityMismatch {
+        /// Number of parameters the function accepts
+        expected: usize,
+        /// Number of arguments that were supplied
+        found: usize,
+    },
+    /// A function was called without a value for a parameter that has no default.
+    MissingArgument(String),
+    /// A named argument does not match any parameter of the function.
+    UnknownArgument(String),
+    /// A parameter was given a value both by position and by name, or twice by name.
+    DuplicateArgument(String),
+    /// An identifier is not bound in any enclosing scope.
+    UnknownIdentifier(String),
+    /// An identifier refers back to itself while being evaluated.
+    CyclicReference(String),
+    /// An index or range bound lies outside of a list.
+    IndexOutOfBounds {
+        /// The offending index
+        index: i64,
+        /// Length of the indexed list
+        len: usize,
+    },
+    /// A module could not be resolved, loaded, parsed or evaluated.
+    ImportFailed {
+        /// The path passed to `import`
+        path: String,
+        /// Description of the failure
+        reason: String,
+    },
+    /// A module imports itself, directly or through other modules.
+    CyclicImport(String),
+    /// Function calls were nested deeper than the given limit, usually by unbounded recursion.
+    CallDepthExceeded(usize),
+    /// Two values without an ordering between them were compared.
+    NotComparable {
+        /// Type of the left operand
+        lhs: &'static str,
+        /// Type of the right operand
+        rhs: &'static str,
+    },
+    /// No arm of a match expression fits the value, which is written as RESL.
+    NoMatch(String),
+    /// A range would produce more items than the given limit.
+    RangeTooLarge(usize),
+    /// An integer operation produced a result outside the range of a 64-bit integer.
+    Overflow,
+    /// A division or remainder had a divisor of zero.
+    DivisionByZero,
+    /// An environment variable was read without the engine allowing it.
+    EnvNotAllowed(String),
+    /// An environment variable without a default is not set.
+    EnvNotSet(String),
+    /// A function registered by the host reported a failure.
+    Custom(String),
+}
+
+impl RuntimeError {
+    /// Creates an error for the expression at `span`.
+    ///
+    /// The location fields are filled in by [`RuntimeError::locate`] once the
+    /// source text is available.
+    pub(crate) fn new(kind: RuntimeErrorKind, span: Span) -> Self {
+        Self {
+            kind,
+            span,
+            line_number: 0,
+            column: 0,
+            line_content: String::new(),
+        }
+    }
+
+    /// Resolves the line and column of the error within `source`.
+    ///
+    /// An error located before keeps its location, as errors raised in closures are
+    /// located within the source of the module declaring them.
+    pub(crate) fn locate(self, source: &str) -> Self {
+        if self.line_number > 0 {
+            return self;
+        }
+
+        let (line_number, column, line_content) = locate(source.as_bytes(), self.span.start());
+
+        Self {
+            line_number,
+            column,
+            line_content,
+            ..self
+        }
+    }
+}
+
+/// Formats the error for display in the same layout as [`ParseError`].
+///
+/// # Example Output
+///
+/// ```text
+/// Error: Type mismatch
+///  --> line 1, column 15
+///   |
+/// 1 | {port = "80"; port + 1}
+///   |               ^^^^
+///   = Expected integer or float, found string
+/// ```
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Error: {}", self.kind.title())?;
+        writeln!(f, " --> line {}, column {}", self.line_number, self.column)?;
+
+        let gutter_width = self.line_number.to_string().len();
+
+        // gutter + code line
+        writeln!(f, "{:>gwidth$} |", "", gwidth = gutter_width)?;
+        writeln!(
+            f,
+            "{:>gwidth$} | {}",
+            self.line_number,
+            self.line_content,
+            gwidth = gutter_width
+        )?;
+
+        // marker spanning the offending expression, clipped to the end of the line
+        let column_offset = self.column.saturating_sub(1);
+        let marker_width = self
+            .span
+            .len()
+            .min(self.line_content.len().saturating_sub(column_offset))
+            .max(1);
+        writeln!(
+            f,
+            "{:>gwidth$} | {:>cwidth$}{}",
+            "",
+            "",
+            "^".repeat(marker_width),
+            gwidth = gutter_width,
+            cwidth = column_offset
+        )?;
+
+        write!(f, "{:>gwidth$} = {}", "", self.kind, gwidth = gutter_width)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+impl RuntimeErrorKind {
+    /// Returns a short headline describing the kind of failure.
+    pub(crate) fn title(&self) -> &'static str {
+        match self {
+            Self::TypeMismatch { .. } => "Type mismatch",
+            Self::ArityMismatch { .. } => "Arity mismatch",
+            Self::MissingArgument(_) => "Missing argument",
+            Self::UnknownArgument(_) => "Unknown argument",
+            Self::DuplicateArgument(_) => "Duplicate argument",
+            Self::UnknownIdentifier(_) => "Unknown identifier",
+            Self::CyclicReference(_) => "Cyclic reference",
+            Self::IndexOutOfBounds { .. } => "Index out of bounds",
+            Self::ImportFailed { .. } => "Import failed",
+            Self::CyclicImport(_) => "Cyclic import",
+            Self::CallDepthExceeded(_) => "Call depth exceeded",
+            Self::NotComparable { .. } => "Incomparable values",
+            Self::NoMatch(_) => "No matching arm",
+            Self::RangeTooLarge(_) => "Range too large",
+            Self::Overflow => "Integer overflow",
+            Self::DivisionByZero => "Division by zero",
+            Self::EnvNotAllowed(_) => "Environment access denied",
+            Self::EnvNotSet(_) => "Missing environment variable",
+            Self::Custom(_) => "Function failed",
+        }
+    }
+}
+
+impl std::fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TypeMismatch { expected, found } => {
+                write!(f, "Expected {expected}, found {found}")
+            }
+            Self::ArityMismatch { expected, found } => {
+                write!(f, "Expected {expected} argument(s), found {found}")
+            }
+            Self::MissingArgument(name) => write!(f, "No value is given for `{name}`"),
+            Self::UnknownArgument(name) => write!(f, "There is no parameter named `{name}`"),
+            Self::DuplicateArgument(name) => write!(f, "`{name}` is given more than once"),
+            Self::UnknownIdentifier(name) => write!(f, "`{name}` is not defined"),
+            Self::CyclicReference(name) => write!(f, "`{name}` refers to itself"),
+            Self::IndexOutOfBounds { index, len } => {
+                write!(f, "Index {index} is out of bounds for length {len}")
+            }
+            Self::ImportFailed { path, reason } => {
+                write!(f, "Cannot import `{path}`: {reason}")
+            }
+            Self::CyclicImport(path) => write!(f, "`{path}` is already being imported"),
+            Self::CallDepthExceeded(limit) => {
+                write!(f, "Function calls are nested more than {limit} levels deep")
+            }
+            Self::NotComparable { lhs, rhs } => write!(f, "Cannot order {lhs} against {rhs}"),
+            Self::NoMatch(value) => write!(f, "No arm matches {value}"),
+            Self::RangeTooLarge(limit) => write!(f, "The range has more than {limit} items"),
+            Self::Overflow => write!(f, "The result does not fit in a 64-bit integer"),
+            Self::DivisionByZero => write!(f, "The divisor is zero"),
+            Self::EnvNotAllowed(name) => write!(f, "Reading `{name}` is not allowed"),
+            Self::EnvNotSet(name) => write!(f, "`{name}` is not set"),
+            Self::Custom(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+/// Represents errors raised while deserializing a value into a Rust type.
+///
+/// The error records the path to the value that failed to deserialize, written with
+/// `.key` for map entries and `[index]` for list elements (e.g. `servers[0].port`).
+/// The path is empty if the top-level value itself failed to deserialize.
+///
+/// # Examples
+///
+/// ```
+/// use resl::{Error, from_str};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Server {
+///     #[allow(dead_code)]
+///     port: u16,
+/// }
+///
+/// let result = from_str::<Vec<Server>>("[[\"port\": 80], [\"port\": \"443\"]]");
+///
+/// match result {
+///     Err(Error::Deserialize(err)) => assert_eq!(err.path, "[1].port"),
+///     _ => panic!("expected a deserialization error"),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeserializeError {
+    /// Description of what went wrong
+    pub message: String,
+    /// Path to the value that failed to deserialize
+    pub path: String,
+}
+
+impl DeserializeError {
+    /// Records `path` as the location of the error unless a deeper one is known.
+    pub(crate) fn at(mut self, path: &str) -> Self {
+        if self.path.is_empty() {
+            self.path = path.to_string();
+        }
+        self
+    }
+}
+
+impl serde::de::Error for DeserializeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+            path: String::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{} at `{}`", self.message, self.path)
+        }
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+/// Represents errors raised while serializing a Rust value into RESL.
+///
+/// Serialization fails for values that have no RESL representation, such as
+/// integers outside the range of a 64-bit signed integer or map keys that are
+/// not strings.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// let map = BTreeMap::from([(vec![1], "one")]);
+///
+/// let err = resl::to_string(&map).unwrap_err();
+/// assert_eq!(err.message, "map key must be a string");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerializeError {
+    /// Description of what went wrong
+    pub message: String,
+}
+
+impl serde::ser::Error for SerializeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SerializeError {}
+
+/// Represents any error produced while parsing or evaluating RESL.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The input could not be parsed.
+    Parse(ParseError),
+    /// The input was parsed but failed to evaluate.
+    Runtime(RuntimeError),
+    /// The input was evaluated but did not match the requested type.
+    Deserialize(DeserializeError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Parse(err) => err.fmt(f),
+            Error::Runtime(err) => err.fmt(f),
+            Error::Deserialize(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(err) => Some(err),
+            Error::Runtime(err) => Some(err),
+            Error::Deserialize(err) => Some(err),
+        }
+    }
+}
+
+impl From<DeserializeError> for Error {
+    fn from(err: DeserializeError) -> Self {
+        Error::Deserialize(err)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(err: RuntimeError) -> Self {
+        Error::Runtime(err)
+    }
+}
+
+/// Finds the line number, column and line content for a byte offset in `input`.
+fn locate(input: &[u8], offset: usize) -> (usize, usize, String) {
+    // Offsets past the end are located at the end rather than panicking
+    let offset = offset.min(input.len());
+
+    let mut line_start_byte = 0;
+    let mut line_end_byte = input.len();
+    let mut line_number = 1;
+    let mut column = 1;
+
+    // Enumerate over the input string from starting to the offset
+    // This is to find the `line_number`, `line_start_byte`, and `column`
+    for (index, byte) in input[0..offset].iter().enumerate() {
+        // Check if byte represents a new line
+        if *byte == b'\n' {
+            // Set `line_start_byte` at the next index of the `\n` char
+            line_start_byte = index + 1;
+            // Increment `line_number` by 1
+            line_number += 1;
+            // Reset `column` to 1
+            column = 1;
+        } else {
+            // Increment `column` by 1
+            column += 1;
+        }
+    }
+
+    // Enumerate over the input string from the offset to the end
+    // This is to find the `line_end_byte`
+    for (index, byte) in input[offset..].iter().enumerate() {
+        // Check if byte represents a new line
+        if *byte == b'\n' {
+            line_end_byte = index + offset;
+            break;
+        }
+    }
+
+    // The content is always valid UTF-8 since the input is guaranteed to be valid UTF-8
+    let line_content =
+        unsafe { str::from_utf8_unchecked(&input[line_start_byte..line_end_byte]) }.to_string();
+
+    (line_number, column, line_content)
+}
@@ -1,12 +1,13 @@
 use winnow::{
     LocatingSlice, ModalResult, Parser,
-    combinator::{alt, cut_err, eof, fail, terminated},
+    combinator::{alt, cut_err, eof, fail, opt, terminated},
 };
 
 use crate::{
     StatefulInput,
     block::Block,
-    error::ParseError,
+    comment::{self, Attachments},
+    error::{EvalResult, ParseError},
     fn_call::FnCall,
     for_each::ForEach,
     function::Fn,
@@ -17,20 +18,33 @@ use crate::{
     list::{self, List},
     macros::{exp_desc, label},
     map::{self, Map},
-    null,
+    match_expr::Match,
+    number,
     prefix::PrefixOp,
+    span::Span,
     state::{CtxState, EvalState, FmtState, ParseState},
+    string::{self, Interpolation},
     utils::delimited_multispace0,
     value::Value,
 };
 
-/// Represents all possible expressions in the RESL language.
+/// Represents an expression in the RESL language.
 ///
 /// Expressions are the building blocks of RESL programs. Every construct in RESL
-/// is an expression that evaluates to a `Value`. This enum encompasses all syntax
-/// elements from literals to complex operations and control structures.
+/// is an expression that evaluates to a `Value`. Each expression pairs its
+/// [`ExprKind`] with the [`Span`] of source text it was parsed from.
 #[derive(Debug, Default, Clone)]
-pub enum Expr {
+pub struct Expr {
+    kind: ExprKind,
+    span: Span,
+}
+
+/// Represents all possible kinds of expressions in the RESL language.
+///
+/// This enum encompasses all syntax elements from literals to complex operations
+/// and control structures.
+#[derive(Debug, Default, Clone)]
+pub enum ExprKind {
     /// The null value literal.
     ///
     /// Examples: `null`
@@ -40,6 +54,10 @@ pub enum Expr {
     ///
     /// Examples: `"hello"`, `"Line 1\nLine 2"`
     Str(String),
+    /// String literals with embedded expressions, converted to strings like `to_str`.
+    ///
+    /// Examples: `"http://${host}:${port}"`
+    Interpolated(Interpolation),
     /// 64-bit signed integer literals.
     ///
     /// Examples: `42`, `-17`
@@ -90,6 +108,11 @@ pub enum Expr {
     ///
     /// Examples: `? condition : "yes" | "no"`
     IfElse(IfElse),
+    /// Match expressions evaluating the first arm whose pattern fits a value.
+    /// Arms may destructure lists and maps, and have a `?` guard.
+    ///
+    /// Examples: `match env { "prod" => 3, _ => 1 }`
+    Match(Match),
     /// For-each loops over lists or maps.
     ///
     /// Examples: `x > (k, v) : concat(k, v)` or `i > (index, item) : item * 2`
@@ -105,35 +128,68 @@ pub enum Expr {
 }
 
 impl Expr {
+    /// Creates an expression of the given kind spanning `span`.
+    pub(crate) fn new(kind: ExprKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    /// Returns the kind of the expression.
+    pub fn kind(&self) -> &ExprKind {
+        &self.kind
+    }
+
+    /// Consumes the expression and returns its kind.
+    pub(crate) fn into_kind(self) -> ExprKind {
+        self.kind
+    }
+
+    /// Returns the byte range of source text the expression was parsed from.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
     /// Parses an expression from the input stream.
     pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Self> {
         delimited_multispace0(alt((
-            // This must be before InfixOp parser because it starts with an Ident
-            ForEach::parse,
-            // This InfixOp parser includes all the remaining exprs.
-            // It will short-circuit in-case no op is found
+            // This parses a ForEach or an InfixOp, which includes all the
+            // remaining exprs. It will short-circuit in-case no op is found
             // Parses:
             // - Str
+            // - Interpolated
             // - Int
             // - Float
             // - Bool
+            // - Null
+            // - Map
+            // - List
             // - Ident
             // - Index
             // - FnCall
             // - PrefixOp
             // - Block
+            // - Match
             // - InfixOp (if operators found)
-            InfixOp::parse,
-            null::parse,
-            map::parse,
-            list::parse,
+            // - ForEach (if a pattern follows the operand or operations)
+            Self::parse_operation,
             IfElse::parse,
+            Fn::parse,
             fail.context(label!("expression"))
                 .context(exp_desc!("a valid expression")),
         )))
         .parse_next(input)
     }
 
+    /// Parses a chain of operations, which is iterated if a for-each pattern follows.
+    fn parse_operation(input: &mut StatefulInput) -> ModalResult<Self> {
+        let operand = InfixOp::parse_operand(input)?;
+        let chain = InfixOp::parse_chain(input, operand, 0)?;
+
+        match opt(ForEach::parse_pattern).parse_next(input)? {
+            Some(pattern) => ForEach::parse_rest(input, chain, pattern),
+            None => Ok(chain),
+        }
+    }
+
     /// Parses an expression with a requirement that one must be present.
     pub(crate) fn require_parse(input: &mut StatefulInput) -> ModalResult<Self> {
         alt((
@@ -146,6 +202,8 @@ impl Expr {
     /// Parses a RESL expression from a string input with a given context state.
     /// This consumes the entire input and returns an error if any unparsed input remains.
     pub(crate) fn parse_all(input: &str, ctx_state: &mut CtxState) -> Result<Self, ParseError> {
+        ctx_state.set_source(input);
+
         let input = StatefulInput {
             input: LocatingSlice::new(input),
             state: ParseState::new(ctx_state),
@@ -163,30 +221,68 @@ impl Expr {
     }
 
     /// Evaluates the expression and returns the computed value.
-    pub(crate) fn evaluate(self, state: &mut EvalState) -> Value {
-        match self {
-            Self::Null => Value::Null,
-            Self::Str(s) => Value::String(s),
-            Self::Int(i) => Value::Integer(i),
-            Self::Float(f) => Value::Float(f),
-            Self::Bool(b) => Value::Boolean(b),
-            Self::List(list) => list::evaluate(list, state),
-            Self::Map(map) => map::evaluate(map, state),
-            Self::Ident(ident) => ident.evaluate(state).cloned().unwrap_or_default(),
-            Self::Index(index) => index.evaluate(state),
-            Self::InfixOp(infix_op) => infix_op.evaluate(state),
-            Self::PrefixOp(prefix_op) => prefix_op.compute(state),
-            Self::Block(block) => block.evaluate(state),
-            Self::IfElse(if_else) => if_else.evaluate(state),
-            Self::ForEach(for_each) => for_each.evaluate(state),
-            Self::Fn(function) => function.evaluate(state),
-            Self::FnCall(fn_call) => fn_call.evaluate(state),
+    pub(crate) fn evaluate(self, state: &mut EvalState) -> EvalResult {
+        let span = self.span;
+        match self.kind {
+            ExprKind::Null => Ok(Value::Null),
+            ExprKind::Str(s) => Ok(Value::String(s)),
+            ExprKind::Interpolated(interpolation) => interpolation.evaluate(state),
+            ExprKind::Int(i) => Ok(Value::Integer(i)),
+            ExprKind::Float(f) => Ok(Value::Float(f)),
+            ExprKind::Bool(b) => Ok(Value::Boolean(b)),
+            ExprKind::List(list) => list::evaluate(list, state),
+            ExprKind::Map(map) => map::evaluate(map, state),
+            ExprKind::Ident(ident) => ident.evaluate(state, span),
+            ExprKind::Index(index) => index.evaluate(state),
+            ExprKind::InfixOp(infix_op) => infix_op.evaluate(state),
+            ExprKind::PrefixOp(prefix_op) => prefix_op.compute(state),
+            ExprKind::Block(block) => block.evaluate(state),
+            ExprKind::IfElse(if_else) => if_else.evaluate(state),
+            ExprKind::Match(match_expr) => match_expr.evaluate(state),
+            ExprKind::ForEach(for_each) => for_each.evaluate(state),
+            ExprKind::Fn(function) => function.evaluate(state),
+            ExprKind::FnCall(fn_call) => fn_call.evaluate(state, span),
+        }
+    }
+
+    /// Returns the expressions directly contained in this one, in source order.
+    pub(crate) fn children<'a>(&'a self, ctx_state: &'a CtxState) -> Vec<&'a Expr> {
+        match &self.kind {
+            ExprKind::Null
+            | ExprKind::Str(_)
+            | ExprKind::Int(_)
+            | ExprKind::Float(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Ident(_) => Vec::new(),
+            ExprKind::Interpolated(interpolation) => interpolation.children(),
+            ExprKind::List(list) => list::children(list),
+            ExprKind::Map(map) => map::children(map),
+            ExprKind::Index(index) => index.children(),
+            ExprKind::InfixOp(infix_op) => infix_op.children(),
+            ExprKind::PrefixOp(prefix_op) => prefix_op.children(),
+            ExprKind::Block(block) => block.children(ctx_state),
+            ExprKind::IfElse(if_else) => if_else.children(),
+            ExprKind::Match(match_expr) => match_expr.children(),
+            ExprKind::ForEach(for_each) => for_each.children(),
+            ExprKind::Fn(function) => function.children(ctx_state),
+            ExprKind::FnCall(fn_call) => fn_call.children(),
         }
     }
 
-    /// Determines if the expression should be cached after evaluation.
-    pub(crate) fn should_be_cached(&self) -> bool {
-        !matches!(self, Self::Fn(_))
+    /// Formats the expression as a whole source text of `len` bytes, keeping the
+    /// comments around it.
+    pub(crate) fn format_all<W: std::fmt::Write>(
+        &self,
+        len: usize,
+        writer: &mut W,
+        state: FmtState,
+    ) -> std::fmt::Result {
+        let comments = Attachments::new(state, Span::new(0, len), &[self.span]);
+
+        comments[0].format_leading(writer, state)?;
+        self.format(writer, state)?;
+        comments[0].format_trailing(writer, state)?;
+        comments.format_dangling(writer, state)
     }
 
     /// Formats the expression to a writer with specified formatting state.
@@ -195,29 +291,29 @@ impl Expr {
         writer: &mut W,
         state: FmtState,
     ) -> std::fmt::Result {
-        match self {
-            Self::Null => write!(writer, "null"),
-            Self::Str(s) => write!(writer, "\"{}\"", s),
-            Self::Int(i) => write!(writer, "{}", i),
-            Self::Float(f) => {
-                if f.fract() == 0.0 {
-                    write!(writer, "{:.1}", f)
-                } else {
-                    write!(writer, "{}", f)
-                }
+        comment::format_node(writer, state, self.span, |writer| match &self.kind {
+            ExprKind::Null => write!(writer, "null"),
+            ExprKind::Str(s) => string::format_literal(writer, state, self.span, s),
+            ExprKind::Interpolated(interpolation) => interpolation.format(self.span, writer, state),
+            ExprKind::Int(i) => {
+                number::write_literal(writer, state, self.span, &Value::Integer(*i))
             }
-            Self::Bool(b) => write!(writer, "{}", b),
-            Self::List(list) => list::format(list, writer, state),
-            Self::Map(map) => map::format(map, writer, state),
-            Self::Ident(ident) => ident.format(writer, state),
-            Self::Index(index) => index.format(writer, state),
-            Self::InfixOp(infix_op) => infix_op.format(writer, state),
-            Self::PrefixOp(prefix_op) => prefix_op.format(writer, state),
-            Self::Block(block) => block.format(writer, state),
-            Self::IfElse(if_else) => if_else.format(writer, state),
-            Self::ForEach(for_each) => for_each.format(writer, state),
-            Self::Fn(func) => func.format(writer, state),
-            Self::FnCall(fn_call) => fn_call.format(writer, state),
-        }
+            ExprKind::Float(f) => {
+                number::write_literal(writer, state, self.span, &Value::Float(*f))
+            }
+            ExprKind::Bool(b) => write!(writer, "{}", b),
+            ExprKind::List(list) => list::format(list, self.span, writer, state),
+            ExprKind::Map(map) => map::format(map, self.span, writer, state),
+            ExprKind::Ident(ident) => ident.format(writer, state),
+            ExprKind::Index(index) => index.format(writer, state),
+            ExprKind::InfixOp(infix_op) => infix_op.format(writer, state),
+            ExprKind::PrefixOp(prefix_op) => prefix_op.format(writer, state),
+            ExprKind::Block(block) => block.format(self.span, writer, state),
+            ExprKind::IfElse(if_else) => if_else.format(writer, state),
+            ExprKind::Match(match_expr) => match_expr.format(self.span, writer, state),
+            ExprKind::ForEach(for_each) => for_each.format(writer, state),
+            ExprKind::Fn(func) => func.format(writer, state),
+            ExprKind::FnCall(fn_call) => fn_call.format(self.span, writer, state),
+        })
     }
 }
@@ -1,81 +1,178 @@
 use winnow::{
     ModalResult, Parser,
-    combinator::{alt, cut_err, delimited, fail, preceded, separated},
+    combinator::{cut_err, fail, not, opt, preceded, separated, terminated},
+    stream::Stream,
 };
 
 use crate::{
-    StatefulInput,
-    expr::Expr,
-    function::Fn,
+    StatefulInput, comment,
+    error::{EvalResult, RuntimeErrorKind},
+    expr::{Expr, ExprKind},
     ident::Ident,
     macros::{exp_char, exp_desc, label},
+    span::{Span, Spanned},
     state::{EvalState, FmtState},
-    utils::delimited_multispace0,
+    utils::{delimited_multispace0, located, spanned, with_bitwise_or},
     value::Value,
 };
 
+/// Argument passed by name, along with the located name.
+type NamedExpr = (Spanned<Ident>, Expr);
+
 /// Function call expression.
 #[derive(Debug, Clone)]
 pub struct FnCall {
-    name: Ident,
+    name: Spanned<Ident>,
     args: Vec<Expr>,
+    /// Arguments passed by name, which follow the positional ones
+    named: Vec<NamedExpr>,
 }
 
 impl FnCall {
     pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-        (
-            delimited_multispace0(Ident::parse_ident),
-            delimited(
-                '(',
-                // Arguments (expressions) separated by commas
-                alt((
-                    delimited_multispace0(separated(1.., Expr::parse, delimited_multispace0(','))),
-                    cut_err(fail).context(exp_desc!("one or more arguments")),
-                )),
-                alt((
-                    // Trailing comma before closing ')'
-                    preceded(
-                        delimited_multispace0(','),
-                        cut_err(')')
-                            .context(exp_desc!("an expression"))
-                            .context(exp_char!(')')),
-                    ),
-                    // No trailing comma
-                    cut_err(')').context(exp_char!(',')).context(exp_char!(')')),
-                )),
-            ),
+        spanned(
+            (
+                delimited_multispace0(located(Ident::parse_ident)),
+                preceded('(', with_bitwise_or(true, Self::parse_args)),
+            )
+                .context(label!("function call"))
+                .map(|(name, (args, named))| Self { name, args, named })
+                .map(ExprKind::FnCall),
         )
-            .context(label!("function call"))
-            .map(|(ident, args)| Self { name: ident, args })
-            .map(Expr::FnCall)
-            .parse_next(input)
+        .parse_next(input)
     }
 
-    pub(crate) fn evaluate(self, state: &mut EvalState) -> Value {
-        if let Some(Expr::Fn(function)) = state.get_expr(&self.name) {
-            match function {
-                Fn::Defined(declared) => {
-                    return declared.to_owned().evaluate(state, self.args);
+    /// Parses the arguments up to the closing `)`, such as `"api", port = 443)`.
+    fn parse_args(input: &mut StatefulInput) -> ModalResult<(Vec<Expr>, Vec<NamedExpr>)> {
+        let mut args = Vec::new();
+        let mut named = Vec::new();
+
+        separated::<_, _, (), _, _, _, _>(
+            0..,
+            |input: &mut StatefulInput| {
+                let start = input.checkpoint();
+
+                let name = opt(terminated(
+                    delimited_multispace0(located(Ident::parse_ident)),
+                    terminated('=', not('=')),
+                ))
+                .parse_next(input)?;
+
+                match name {
+                    Some(name) => named.push((name, Expr::require_parse(input)?)),
+                    None => {
+                        let arg = Expr::parse(input)?;
+
+                        // Positional arguments cannot follow named ones
+                        if !named.is_empty() {
+                            input.reset(&start);
+                            return cut_err(fail)
+                                .context(exp_desc!("named argument"))
+                                .parse_next(input);
+                        }
+                        args.push(arg);
+                    }
                 }
-                Fn::BuiltIn(func) => return func(state, self.args),
-            }
+
+                Ok(())
+            },
+            delimited_multispace0(','),
+        )
+        .parse_next(input)?;
+
+        if args.is_empty() && named.is_empty() {
+            cut_err(delimited_multispace0(')'))
+                .context(exp_desc!("an argument"))
+                .context(exp_char!(')'))
+                .parse_next(input)?;
+        } else {
+            // Allow a trailing comma
+            opt(delimited_multispace0(',')).parse_next(input)?;
+            cut_err(')')
+                .context(exp_char!(','))
+                .context(exp_char!(')'))
+                .parse_next(input)?;
         }
-        Value::Null
+
+        Ok((args, named))
+    }
+
+    pub(crate) fn evaluate(self, state: &mut EvalState, span: Span) -> EvalResult {
+        let closure = match self.name.node.evaluate(state, self.name.span)? {
+            Value::Function(closure) => closure,
+            // The identifier is either unbound or bound to something other than a function
+            value => {
+                return state.raise(
+                    RuntimeErrorKind::TypeMismatch {
+                        expected: "function",
+                        found: value.type_name(),
+                    },
+                    self.name.span,
+                );
+            }
+        };
+
+        // Arguments are evaluated in the environment of the caller
+        let args = self
+            .args
+            .into_iter()
+            .map(|arg| {
+                let span = arg.span();
+                Ok((arg.evaluate(state)?, span))
+            })
+            .collect::<EvalResult<Vec<_>>>()?;
+
+        // Names are passed as text, since the function may be declared in another module
+        let named = self
+            .named
+            .into_iter()
+            .map(|(name, arg)| {
+                let name = Spanned {
+                    node: state.resolve_ident(&name.node).to_string(),
+                    span: name.span,
+                };
+                Ok((name, arg.evaluate(state)?))
+            })
+            .collect::<EvalResult<Vec<_>>>()?;
+
+        closure.call(state, span, args, named)
+    }
+
+    /// Returns the arguments, in source order.
+    pub(crate) fn children(&self) -> Vec<&Expr> {
+        self.args
+            .iter()
+            .chain(self.named.iter().map(|(_, arg)| arg))
+            .collect()
     }
 
     pub(crate) fn format<W: std::fmt::Write>(
         &self,
+        span: Span,
         writer: &mut W,
         state: FmtState,
     ) -> std::fmt::Result {
         let pretty = state.pretty();
-        self.name.format(writer, state)?;
+        comment::format_node(writer, state, self.name.span, |writer| {
+            self.name.node.format(writer, state)
+        })?;
 
         write!(writer, "(")?;
 
-        let mut args_iter = self.args.iter().peekable();
+        let mut args_iter = self
+            .args
+            .iter()
+            .map(|arg| (None, arg))
+            .chain(self.named.iter().map(|(name, arg)| (Some(name), arg)))
+            .peekable();
 
-        while let Some(arg) = args_iter.next() {
+        while let Some((name, arg)) = args_iter.next() {
+            if let Some(name) = name {
+                comment::format_node(writer, state, name.span, |writer| {
+                    name.node.format(writer, state)
+                })?;
+                write!(writer, "{}", if pretty { " = " } else { "=" })?;
+            }
             arg.format(writer, state.indented())?;
 
             if args_iter.peek().is_some() {
@@ -86,6 +183,9 @@ impl FnCall {
             }
         }
 
+        let spaced = !self.args.is_empty() || !self.named.is_empty();
+        comment::format_within(writer, state, span.end(), spaced)?;
+
         write!(writer, ")")
     }
 }
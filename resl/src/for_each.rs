@@ -1,29 +1,83 @@
 use winnow::{
     ModalResult, Parser,
-    combinator::{cut_err, delimited, separated_pair},
+    combinator::{cut_err, delimited, opt, preceded, separated_pair},
+    stream::Location,
 };
 
 use crate::{
     Expr, StatefulInput, Value,
     binding::Binding,
+    comment,
     context::Context,
+    document::NameKind,
+    error::{EvalResult, RuntimeErrorKind},
+    expr::ExprKind,
     ident::Ident,
     macros::{exp_char, exp_desc},
+    span::{Span, Spanned},
     state::{EvalState, FmtState},
-    utils::delimited_multispace0,
+    utils::{delimited_multispace0, located},
     value::{ValueList, ValueMap},
 };
 
 // For-each expression for iterating over lists or maps.
 #[derive(Debug, Clone)]
 pub struct ForEach {
-    base: Ident,
+    base: Box<Expr>,
     ctx_idx: usize,
-    body: Box<Expr>,
+    /// Spans of the key and value names
+    names: [Span; 2],
+    /// Condition an item must meet to be kept
+    guard: Option<Box<Expr>>,
+    body: Body,
+}
+
+/// Expression computing the output for each item.
+#[derive(Debug, Clone)]
+enum Body {
+    /// An element, or a value kept under the original key when iterating a map
+    Item(Box<Expr>),
+    /// A key and a value, producing a map
+    Entry(Box<Expr>, Box<Expr>),
 }
 
 impl ForEach {
-    pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
+    /// Parses the `> (key, value)` pattern following a base expression.
+    ///
+    /// Backtracks until the pattern is certain, so that `a > b` and `a > (b)` can
+    /// still be parsed as comparisons.
+    pub(crate) fn parse_pattern(
+        input: &mut StatefulInput,
+    ) -> ModalResult<(Spanned<Ident>, Spanned<Ident>)> {
+        let (key_ident, value_ident) = preceded(
+            delimited_multispace0('>'),
+            delimited(
+                '(',
+                delimited_multispace0(separated_pair(
+                    // Identifier for key/index
+                    located(Ident::parse_ident),
+                    delimited_multispace0(','),
+                    // Identifier for value/element
+                    cut_err(located(Ident::parse_ident)).context(exp_desc!("item identifier")),
+                )),
+                cut_err(')').context(exp_char!(')')),
+            ),
+        )
+        .parse_next(input)?;
+
+        input.state.record_ident(&key_ident, NameKind::Parameter);
+        input.state.record_ident(&value_ident, NameKind::Parameter);
+
+        Ok((key_ident, value_ident))
+    }
+
+    /// Parses the guard and body of a for-each over `base`, whose pattern binds
+    /// `key_ident` and `value_ident`.
+    pub(crate) fn parse_rest(
+        input: &mut StatefulInput,
+        base: Expr,
+        (key_ident, value_ident): (Spanned<Ident>, Spanned<Ident>),
+    ) -> ModalResult<Expr> {
         // Store the current active context index before parsing
         let current_ctx_idx = input.state.active_ctx_idx();
 
@@ -39,31 +93,23 @@ impl ForEach {
 
         // Parse the expression without unwrapping the result
         // This allows restoring the state later
-        let parse_result = separated_pair(
-            Ident::parse_ident,
-            delimited_multispace0('>'),
-            separated_pair(
-                delimited(
-                    cut_err('(').context(exp_char!('(')),
-                    delimited_multispace0(separated_pair(
-                        // Identifier for key/index
-                        cut_err(Ident::parse_ident).context(exp_desc!("index identifier")),
-                        delimited_multispace0(cut_err(',').context(exp_char!(','))),
-                        // Identifier for value/element
-                        cut_err(Ident::parse_ident).context(exp_desc!("item identifier")),
-                    )),
-                    cut_err(')').context(exp_char!(')')),
-                ),
-                delimited_multispace0(cut_err(":").context(exp_char!(':'))),
+        let parse_result = (
+            // Optional guard
+            opt(preceded(
+                delimited_multispace0('?'),
                 Expr::require_parse.map(Box::new),
+            )),
+            preceded(
+                delimited_multispace0(cut_err(":").context(exp_char!(':'))),
+                Self::parse_body,
             ),
         )
-        .parse_next(input);
+            .parse_next(input);
 
         // Restore active context to previous one
         input.state.set_active_ctx(current_ctx_idx);
 
-        let (base, ((key_ident, value_ident), body)) = parse_result.inspect_err(|_| {
+        let (guard, body) = parse_result.inspect_err(|_| {
             // Returned backtrack error during parsing
 
             // Decrement avail_ctx_idx to avoid skipping indices
@@ -74,77 +120,175 @@ impl ForEach {
         let ctx = Context::from_iter(
             Some(current_ctx_idx),
             [
-                (key_ident, Binding::default()),
-                (value_ident, Binding::default()),
+                (key_ident.node, Binding::default()),
+                (value_ident.node, Binding::default()),
             ],
         );
 
         // Place the context at the specified index
         input.state.place_ctx(ctx_idx, ctx);
 
-        Ok(Expr::ForEach(Self {
-            base,
-            ctx_idx,
-            body,
-        }))
+        let span = Span::new(base.span().start(), input.previous_token_end());
+        Ok(Expr::new(
+            ExprKind::ForEach(Self {
+                base: Box::new(base),
+                ctx_idx,
+                names: [key_ident.span, value_ident.span],
+                guard,
+                body,
+            }),
+            span,
+        ))
     }
 
-    pub(crate) fn evaluate(self, state: &mut EvalState) -> Value {
-        let base_value = match self.base.evaluate(state) {
-            Some(value) => match value {
-                Value::List(_) | Value::Map(_) => value.clone(),
-                _ => return Value::Null,
-            },
-            _ => return Value::Null,
-        };
+    /// Parses an item expression, or a key and value expression separated by `:`.
+    fn parse_body(input: &mut StatefulInput) -> ModalResult<Body> {
+        (
+            Expr::require_parse.map(Box::new),
+            opt(preceded(
+                delimited_multispace0(':'),
+                Expr::require_parse.map(Box::new),
+            )),
+        )
+            .map(|(body, value)| match value {
+                Some(value) => Body::Entry(body, value),
+                None => Body::Item(body),
+            })
+            .parse_next(input)
+    }
 
-        // Save the index of the current active ctx
-        let current_ctx_idx = state.active_ctx_idx();
+    pub(crate) fn evaluate(self, state: &mut EvalState) -> EvalResult {
+        let base_span = self.base.span();
+        let base_value = match self.base.as_ref().clone().evaluate(state)? {
+            value @ (Value::List(_) | Value::Map(_)) => value,
+            value => {
+                return state.raise(
+                    RuntimeErrorKind::TypeMismatch {
+                        expected: "list or map",
+                        found: value.type_name(),
+                    },
+                    base_span,
+                );
+            }
+        };
 
-        // Set self as the active context
-        state.set_active_ctx(self.ctx_idx);
+        match base_value {
+            Value::List(list) => self.evaluate_items(
+                list.into_iter()
+                    .enumerate()
+                    .map(|(index, element)| (Value::Integer(index as i64), element)),
+                state,
+            ),
+            Value::Map(map) => self.evaluate_items(
+                map.into_iter()
+                    .map(|(key, value)| (Value::String(key), value)),
+                state,
+            ),
+            _ => unreachable!("This is ensured by the match at the beginning"),
+        }
+    }
 
-        let value = match base_value {
-            Value::List(list) => {
-                let mut value_list = ValueList::new();
-                for (index, element) in list.iter().enumerate() {
-                    // Assign the index and element values to the context
-                    state[self.ctx_idx]
-                        .assign_from_iter([Value::Integer(index as i64), element.to_owned()]);
+    /// Evaluates the body for every key/index and value pair that passes the guard.
+    ///
+    /// The result is a list when iterating a list, and a map when iterating a map or
+    /// when the body computes entries.
+    fn evaluate_items(
+        &self,
+        items: impl Iterator<Item = (Value, Value)>,
+        state: &mut EvalState,
+    ) -> EvalResult {
+        let mut list = ValueList::new();
+        let mut map = ValueMap::new();
+        let mut is_map = matches!(self.body, Body::Entry(..));
+
+        let parent = state.env().clone();
+        let params = state[self.ctx_idx].keys().cloned().collect::<Vec<_>>();
+
+        for (key, value) in items {
+            // Bind the key/index and value in a new environment for this item
+            let args = params.iter().cloned().zip([key.clone(), value]);
+            let env = state.new_env(self.ctx_idx, parent.clone(), args);
+            match state.with_env(env, |state| self.evaluate_item(key, state))? {
+                Some((Some(key), value)) => {
+                    is_map = true;
+                    map.insert(key, value);
+                }
+                Some((None, value)) => list.push(value),
+                None => {}
+            }
+        }
 
-                    // Evaluate the expression in the context of this block
-                    let value = self.body.to_owned().evaluate(state);
+        Ok(if is_map {
+            Value::Map(map)
+        } else {
+            Value::List(list)
+        })
+    }
 
-                    // Push the evaluated value to the result list
-                    value_list.push(value);
+    /// Evaluates the guard and body for a single item in the active environment.
+    ///
+    /// Returns `None` if the item is left out, otherwise the output along with its
+    /// key if the output is a map entry.
+    fn evaluate_item(
+        &self,
+        key: Value,
+        state: &mut EvalState,
+    ) -> EvalResult<Option<(Option<String>, Value)>> {
+        if let Some(guard) = &self.guard {
+            let guard_span = guard.span();
+            match guard.as_ref().clone().evaluate(state)? {
+                Value::Boolean(true) => {}
+                Value::Boolean(false) => return Ok(None),
+                value => {
+                    state.raise(
+                        RuntimeErrorKind::TypeMismatch {
+                            expected: "boolean",
+                            found: value.type_name(),
+                        },
+                        guard_span,
+                    )?;
+                    return Ok(None);
                 }
-                Value::List(value_list)
             }
-            Value::Map(map) => {
-                let mut value_map = ValueMap::new();
-                for (key, val) in map.iter() {
-                    // Assign the key and value to the context
-                    state[self.ctx_idx]
-                        .assign_from_iter([Value::String(key.to_owned()), val.to_owned()]);
-
-                    // Evaluate the expression in the context of this block
-                    let value = self.body.to_owned().evaluate(state);
-
-                    // Push the evaluated value to the result list
-                    value_map.insert(key.to_owned(), value);
+        }
+
+        match (&self.body, key) {
+            (Body::Item(body), Value::String(key)) => {
+                Ok(Some((Some(key), body.as_ref().clone().evaluate(state)?)))
+            }
+            (Body::Item(body), _) => Ok(Some((None, body.as_ref().clone().evaluate(state)?))),
+            (Body::Entry(key_expr, value_expr), _) => {
+                let key_span = key_expr.span();
+                match key_expr.as_ref().clone().evaluate(state)? {
+                    Value::String(key) => Ok(Some((
+                        Some(key),
+                        value_expr.as_ref().clone().evaluate(state)?,
+                    ))),
+                    // Entries without a valid key are left out in lenient mode
+                    value => {
+                        state.raise(
+                            RuntimeErrorKind::TypeMismatch {
+                                expected: "string",
+                                found: value.type_name(),
+                            },
+                            key_span,
+                        )?;
+                        Ok(None)
+                    }
                 }
-                Value::Map(value_map)
             }
-            _ => unreachable!("This is ensured by the match at the beginning"),
-        };
-
-        // Reset active context
-        state.set_active_ctx(current_ctx_idx);
-
-        // Reset the context expressions to Null
-        state[self.ctx_idx].reassign_default_expr();
+        }
+    }
 
-        value
+    /// Returns the base, the guard and the body, in source order.
+    pub(crate) fn children(&self) -> Vec<&Expr> {
+        let mut children = vec![self.base.as_ref()];
+        children.extend(self.guard.as_deref());
+        match &self.body {
+            Body::Item(body) => children.push(body),
+            Body::Entry(key, value) => children.extend([key.as_ref(), value.as_ref()]),
+        }
+        children
     }
 
     pub(crate) fn format<W: std::fmt::Write>(
@@ -166,10 +310,10 @@ impl ForEach {
 
         write!(writer, "(")?;
 
-        let mut params_iter = state[self.ctx_idx].keys().peekable();
+        let mut params_iter = state[self.ctx_idx].keys().zip(self.names).peekable();
 
-        while let Some(param) = params_iter.next() {
-            param.format(writer, state)?;
+        while let Some((param, span)) = params_iter.next() {
+            comment::format_node(writer, state, span, |writer| param.format(writer, state))?;
 
             if params_iter.peek().is_some() {
                 write!(writer, ",")?;
@@ -181,6 +325,20 @@ impl ForEach {
 
         write!(writer, ")")?;
 
+        if let Some(guard) = &self.guard {
+            if state.pretty() {
+                write!(writer, " ")?;
+            }
+
+            write!(writer, "?")?;
+
+            if state.pretty() {
+                write!(writer, " ")?;
+            }
+
+            guard.format(writer, state)?;
+        }
+
         if state.pretty() {
             write!(writer, " ")?;
         }
@@ -191,6 +349,16 @@ impl ForEach {
             write!(writer, " ")?;
         }
 
-        self.body.format(writer, state)
+        match &self.body {
+            Body::Item(body) => body.format(writer, state),
+            Body::Entry(key, value) => {
+                key.format(writer, state)?;
+                write!(writer, ":")?;
+                if state.pretty() {
+                    write!(writer, " ")?;
+                }
+                value.format(writer, state)
+            }
+        }
     }
 }
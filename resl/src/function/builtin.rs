@@ -1,6 +1,13 @@
-use crate::{expr::Expr, function::Fn, state::EvalState, value::Value};
+use crate::{
+    error::{EvalResult, RuntimeErrorKind},
+    function::{Arg, Fn, closure::Closure},
+    module,
+    span::Span,
+    state::EvalState,
+    value::Value,
+};
 
-pub(crate) const BUILTIN_FUNCTIONS: [(&str, Fn); 7] = [
+pub(crate) const BUILTIN_FUNCTIONS: [(&str, Fn); 16] = [
     ("debug", Fn::BuiltIn(debug)),
     ("type_of", Fn::BuiltIn(type_of)),
     ("length", Fn::BuiltIn(length)),
@@ -8,127 +15,365 @@ pub(crate) const BUILTIN_FUNCTIONS: [(&str, Fn); 7] = [
     ("concat", Fn::BuiltIn(concat)),
     ("push", Fn::BuiltIn(push)),
     ("insert", Fn::BuiltIn(insert)),
+    ("flatten", Fn::BuiltIn(flatten)),
+    ("import", Fn::BuiltIn(import)),
+    ("env", Fn::BuiltIn(env)),
+    ("map", Fn::BuiltIn(map)),
+    ("filter", Fn::BuiltIn(filter)),
+    ("reduce", Fn::BuiltIn(reduce)),
+    ("sort_by", Fn::BuiltIn(sort_by)),
+    ("any", Fn::BuiltIn(any)),
+    ("all", Fn::BuiltIn(all)),
 ];
 
-pub(crate) fn debug(state: &mut EvalState, args: Vec<Expr>) -> Value {
-    if args.len() != 1 {
-        return Value::Null;
+/// Takes exactly `N` arguments of a call.
+///
+/// Returns `None` if the number of arguments does not match in lenient mode.
+fn expect_args<const N: usize>(
+    state: &EvalState,
+    span: Span,
+    args: Vec<Arg>,
+) -> EvalResult<Option<[Arg; N]>> {
+    if args.len() != N {
+        return state
+            .raise(
+                RuntimeErrorKind::ArityMismatch {
+                    expected: N,
+                    found: args.len(),
+                },
+                span,
+            )
+            .map(|_| None);
     }
 
-    let value = args[0].to_owned().evaluate(state);
-    println!("{}", value);
-    value
+    Ok(args.try_into().ok())
 }
 
-pub(crate) fn type_of(state: &mut EvalState, args: Vec<Expr>) -> Value {
-    if args.len() != 1 {
-        return Value::Null;
-    }
-
-    let arg = args[0].to_owned().evaluate(state);
+/// Reports an argument of an unsupported type.
+fn mismatch(state: &EvalState, expected: &'static str, (value, span): Arg) -> EvalResult {
+    state.raise(
+        RuntimeErrorKind::TypeMismatch {
+            expected,
+            found: value.type_name(),
+        },
+        span,
+    )
+}
 
-    let type_str = match arg {
-        Value::Null => "null",
-        Value::Boolean(_) => "boolean",
-        Value::Integer(_) => "integer",
-        Value::Float(_) => "float",
-        Value::String(_) => "string",
-        Value::List(_) => "list",
-        Value::Map(_) => "map",
+pub(crate) fn debug(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([(value, _)]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
     };
 
-    Value::String(type_str.to_string())
+    println!("{}", value);
+    Ok(value)
 }
 
-pub(crate) fn length(state: &mut EvalState, args: Vec<Expr>) -> Value {
-    if args.len() != 1 {
-        return Value::Null;
-    }
+pub(crate) fn type_of(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([(arg, _)]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
+
+    Ok(Value::String(arg.type_name().to_string()))
+}
 
-    let arg = args[0].to_owned().evaluate(state);
+pub(crate) fn length(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([arg]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
 
     match arg {
-        Value::String(s) => Value::Integer(s.chars().count() as i64),
-        Value::List(arr) => Value::Integer(arr.len() as i64),
-        Value::Map(map) => Value::Integer(map.len() as i64),
-        _ => Value::Null,
+        (Value::String(s), _) => Ok(Value::Integer(s.chars().count() as i64)),
+        (Value::List(arr), _) => Ok(Value::Integer(arr.len() as i64)),
+        (Value::Map(map), _) => Ok(Value::Integer(map.len() as i64)),
+        arg => mismatch(state, "string, list or map", arg),
     }
 }
 
-pub(crate) fn to_str(state: &mut EvalState, args: Vec<Expr>) -> Value {
-    if args.len() != 1 {
-        return Value::Null;
-    }
+pub(crate) fn to_str(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([(arg, _)]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
 
-    let arg = args[0].to_owned().evaluate(state);
-    Value::String(arg.to_string())
+    Ok(Value::String(arg.stringify()))
 }
 
-pub(crate) fn concat(state: &mut EvalState, args: Vec<Expr>) -> Value {
+pub(crate) fn concat(state: &mut EvalState, _span: Span, args: Vec<Arg>) -> EvalResult {
     let mut string = String::new();
 
     for arg in args {
-        if let Value::String(str) = arg.evaluate(state) {
-            string.push_str(&str)
+        match arg {
+            (Value::String(str), _) => string.push_str(&str),
+            arg => {
+                mismatch(state, "string", arg)?;
+            }
         }
     }
 
     if string.is_empty() {
-        return Value::Null;
+        return Ok(Value::Null);
     }
 
-    Value::String(string)
+    Ok(Value::String(string))
 }
 
-pub(crate) fn push(state: &mut EvalState, args: Vec<Expr>) -> Value {
-    if args.len() != 2 {
-        return Value::Null;
-    }
-
-    let collection = args[0].to_owned().evaluate(state);
-    let value = args[1].to_owned().evaluate(state);
+pub(crate) fn push(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([collection, (value, _)]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
 
     match collection {
-        Value::List(mut arr) => {
+        (Value::List(mut arr), _) => {
             arr.push(value);
-            Value::List(arr)
+            Ok(Value::List(arr))
         }
-        _ => Value::Null,
+        collection => mismatch(state, "list", collection),
     }
 }
 
-pub(crate) fn insert(state: &mut EvalState, args: Vec<Expr>) -> Value {
-    if args.len() != 3 {
-        return Value::Null;
-    }
-
-    let collection = args[0].to_owned().evaluate(state);
-    let key = args[1].to_owned().evaluate(state);
-    let value = args[2].to_owned().evaluate(state);
+pub(crate) fn insert(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([collection, key, (value, _)]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
 
     match collection {
-        Value::Map(mut map) => {
-            if let Value::String(key_str) = key {
+        (Value::Map(mut map), _) => match key {
+            (Value::String(key_str), _) => {
                 map.insert(key_str, value);
-                Value::Map(map)
-            } else {
-                Value::Null
+                Ok(Value::Map(map))
             }
-        }
-        Value::List(mut arr) => {
-            if let Value::Integer(index) = key {
+            key => mismatch(state, "string", key),
+        },
+        (Value::List(mut arr), _) => match key {
+            (Value::Integer(index), key_span) => {
                 let idx = if index < 0 {
-                    (arr.len() as i64 + index) as usize
+                    usize::try_from(arr.len() as i64 + index).ok()
                 } else {
-                    index as usize
+                    usize::try_from(index).ok()
                 };
-                if idx <= arr.len() {
-                    arr.insert(idx, value);
-                    return Value::List(arr);
+                match idx {
+                    Some(idx) if idx <= arr.len() => {
+                        arr.insert(idx, value);
+                        Ok(Value::List(arr))
+                    }
+                    _ => state.raise(
+                        RuntimeErrorKind::IndexOutOfBounds {
+                            index,
+                            len: arr.len(),
+                        },
+                        key_span,
+                    ),
                 }
             }
-            Value::Null
+            key => mismatch(state, "integer", key),
+        },
+        collection => mismatch(state, "list or map", collection),
+    }
+}
+
+pub(crate) fn flatten(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([list]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
+
+    match list {
+        // Only one level is flattened, and items that are not lists are kept
+        (Value::List(list), _) => Ok(Value::List(
+            list.into_iter()
+                .flat_map(|item| match item {
+                    Value::List(items) => items,
+                    item => vec![item],
+                })
+                .collect(),
+        )),
+        list => mismatch(state, "list", list),
+    }
+}
+
+pub(crate) fn import(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([path]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
+
+    match path {
+        (Value::String(path), _) => module::import(state, &path, span),
+        path => mismatch(state, "string", path),
+    }
+}
+
+pub(crate) fn env(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    // The default is only used when the variable is allowed but not set
+    let (name, default) = if args.len() == 1 {
+        let Some([name]) = expect_args(state, span, args)? else {
+            return Ok(Value::Null);
+        };
+        (name, None)
+    } else {
+        let Some([name, (default, _)]) = expect_args(state, span, args)? else {
+            return Ok(Value::Null);
+        };
+        (name, Some(default))
+    };
+
+    let name = match name {
+        (Value::String(name), name_span) => {
+            if !state.engine().is_env_allowed(&name) {
+                return state.raise(RuntimeErrorKind::EnvNotAllowed(name), name_span);
+            }
+            name
         }
-        _ => Value::Null,
+        name => return mismatch(state, "string", name),
+    };
+
+    match (std::env::var(&name), default) {
+        (Ok(value), _) => Ok(Value::String(value)),
+        (Err(_), Some(default)) => Ok(default),
+        (Err(_), None) => state.raise(RuntimeErrorKind::EnvNotSet(name), span),
     }
 }
+
+/// List argument of a higher-order function along with its span.
+type ListArg = (Vec<Value>, Span);
+
+/// Function argument of a higher-order function along with its span.
+type FnArg = (Closure, Span);
+
+/// Checks that a higher-order function is given a list and a function.
+///
+/// Returns `None` if either has another type in lenient mode.
+fn list_and_fn(state: &EvalState, list: Arg, func: Arg) -> EvalResult<Option<(ListArg, FnArg)>> {
+    match (list, func) {
+        ((Value::List(list), list_span), (Value::Function(func), func_span)) => {
+            Ok(Some(((list, list_span), (func, func_span))))
+        }
+        ((Value::List(_), _), func) => mismatch(state, "function", func).map(|_| None),
+        (list, _) => mismatch(state, "list", list).map(|_| None),
+    }
+}
+
+/// Calls a function argument, reporting failures of the call at the argument.
+fn call(state: &mut EvalState, (func, span): &FnArg, args: Vec<Arg>) -> EvalResult {
+    func.clone().call(state, *span, args, Vec::new())
+}
+
+/// Calls a predicate on an item, which must return a boolean.
+///
+/// Other results are reported as a mismatch, and count as `false` in lenient mode.
+fn test(state: &mut EvalState, func: &FnArg, item: Arg) -> EvalResult<bool> {
+    match call(state, func, vec![item])? {
+        Value::Boolean(b) => Ok(b),
+        value => mismatch(state, "boolean", (value, func.1)).map(|_| false),
+    }
+}
+
+pub(crate) fn map(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([list, func]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
+    let Some(((list, list_span), func)) = list_and_fn(state, list, func)? else {
+        return Ok(Value::Null);
+    };
+
+    list.into_iter()
+        .map(|item| call(state, &func, vec![(item, list_span)]))
+        .collect::<EvalResult<_>>()
+        .map(Value::List)
+}
+
+pub(crate) fn filter(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([list, func]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
+    let Some(((list, list_span), func)) = list_and_fn(state, list, func)? else {
+        return Ok(Value::Null);
+    };
+
+    let mut filtered = Vec::new();
+    for item in list {
+        if test(state, &func, (item.clone(), list_span))? {
+            filtered.push(item);
+        }
+    }
+
+    Ok(Value::List(filtered))
+}
+
+pub(crate) fn reduce(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([list, (init, init_span), func]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
+    let Some(((list, list_span), func)) = list_and_fn(state, list, func)? else {
+        return Ok(Value::Null);
+    };
+
+    list.into_iter().try_fold(init, |acc, item| {
+        call(state, &func, vec![(acc, init_span), (item, list_span)])
+    })
+}
+
+pub(crate) fn sort_by(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([list, func]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
+    let Some(((list, list_span), func)) = list_and_fn(state, list, func)? else {
+        return Ok(Value::Null);
+    };
+
+    let mut keyed = list
+        .into_iter()
+        .map(|item| Ok((call(state, &func, vec![(item.clone(), list_span)])?, item)))
+        .collect::<EvalResult<Vec<_>>>()?;
+
+    // The sort is stable, so items with equal keys keep their order
+    // Keys that cannot be compared still need a consistent order for the sort,
+    // which compares neighbours of different types at least once
+    let mut error = None;
+    keyed.sort_by(|(lhs, _), (rhs, _)| {
+        if let Err(kind) = lhs.compare(rhs) {
+            error.get_or_insert(kind);
+        }
+        lhs.total_cmp(rhs)
+    });
+
+    if let Some(kind) = error {
+        return state.raise(kind, func.1);
+    }
+
+    Ok(Value::List(
+        keyed.into_iter().map(|(_, item)| item).collect(),
+    ))
+}
+
+pub(crate) fn any(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([list, func]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
+    let Some(((list, list_span), func)) = list_and_fn(state, list, func)? else {
+        return Ok(Value::Null);
+    };
+
+    for item in list {
+        if test(state, &func, (item, list_span))? {
+            return Ok(Value::Boolean(true));
+        }
+    }
+
+    Ok(Value::Boolean(false))
+}
+
+pub(crate) fn all(state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+    let Some([list, func]) = expect_args(state, span, args)? else {
+        return Ok(Value::Null);
+    };
+    let Some(((list, list_span), func)) = list_and_fn(state, list, func)? else {
+        return Ok(Value::Null);
+    };
+
+    for item in list {
+        if !test(state, &func, (item, list_span))? {
+            return Ok(Value::Boolean(false));
+        }
+    }
+
+    Ok(Value::Boolean(true))
+}
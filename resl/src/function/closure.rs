@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use crate::{
+    env::Env,
+    error::{EvalResult, RuntimeErrorKind},
+    function::{Arg, Fn, NamedArg},
+    span::Span,
+    state::{CtxState, EvalState},
+};
+
+/// Function value, keeping the environment the function was declared in.
+///
+/// Evaluating a function declaration or a name bound to a function produces a
+/// closure, which can be called, passed as an argument, returned or stored in
+/// collections. Calling it evaluates the body with the bindings visible where it
+/// was declared, even if that scope has been left since.
+#[derive(Clone)]
+pub struct Closure {
+    function: Fn,
+    env: Arc<Env>,
+    ctx_state: Arc<CtxState>,
+}
+
+impl Closure {
+    /// Creates a closure over the active environment.
+    pub(crate) fn new(function: Fn, state: &EvalState) -> Self {
+        Self {
+            function,
+            env: state.env().clone(),
+            ctx_state: state.ctx_state().clone(),
+        }
+    }
+
+    /// Calls the function with evaluated arguments, reporting failures at `span`.
+    ///
+    /// Only declared functions have parameter names, so other functions fail if
+    /// given named arguments.
+    pub(crate) fn call(
+        self,
+        state: &mut EvalState,
+        span: Span,
+        args: Vec<Arg>,
+        named: Vec<NamedArg>,
+    ) -> EvalResult {
+        if let Some((name, _)) = named.first()
+            && !matches!(self.function, Fn::Defined(_))
+        {
+            return state.raise(
+                RuntimeErrorKind::UnknownArgument(name.node.clone()),
+                name.span,
+            );
+        }
+
+        match self.function {
+            Fn::Defined(defined) => state.with_ctx_state(self.ctx_state, |state| {
+                defined.evaluate(state, span, args, named, self.env)
+            }),
+            Fn::BuiltIn(func) => func(state, span, args),
+            Fn::Native(native) => native.evaluate(state, span, args),
+        }
+    }
+}
+
+impl std::fmt::Debug for Closure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Closure")
+            .field("function", &self.function)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Functions are never equal, not even to themselves.
+impl PartialEq for Closure {
+    fn eq(&self, _other: &Self) -> bool {
+        false
+    }
+}
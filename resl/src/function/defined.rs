@@ -1,17 +1,26 @@
+use std::sync::Arc;
+
 use winnow::{
     ModalResult, Parser,
-    combinator::{alt, cut_err, delimited, fail, preceded, separated},
+    combinator::{cut_err, fail, not, opt, preceded, separated, terminated},
+    stream::Stream,
 };
 
 use crate::{
     StatefulInput,
     binding::Binding,
+    comment,
     context::Context,
+    document::NameKind,
+    env::Env,
+    error::{EvalResult, RuntimeErrorKind},
     expr::Expr,
+    function::{Arg, NamedArg},
     ident::Ident,
     macros::{exp_char, exp_desc, label},
-    state::{EvalState, FmtState},
-    utils::delimited_multispace0,
+    span::{Span, Spanned},
+    state::{CtxState, EvalState, FmtState},
+    utils::{delimited_multispace0, located, with_bitwise_or},
     value::Value,
 };
 
@@ -19,9 +28,24 @@ use crate::{
 #[derive(Debug, Clone)]
 pub struct Defined {
     ctx_idx: usize,
+    params: Vec<(Spanned<Ident>, ParamKind)>,
     body: Box<Expr>,
 }
 
+/// Parsed parameter along with its default value, if any.
+type Param = (Spanned<Ident>, ParamKind, Option<Expr>);
+
+/// How a parameter of a declared function receives its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParamKind {
+    /// Parameter that must be given an argument.
+    Required,
+    /// Parameter with a default, evaluated in the call if no argument is given.
+    Optional,
+    /// Parameter collecting the remaining positional arguments into a list.
+    Rest,
+}
+
 impl Defined {
     pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Self> {
         // Store the current active context index before parsing the block
@@ -41,26 +65,7 @@ impl Defined {
         // Parse the block without unwrapping the result
         // This allows restoring the state variables later
         let parse_result = (
-            delimited(
-                '|',
-                alt((
-                    delimited_multispace0(separated(
-                        1..,
-                        Ident::parse_ident,
-                        delimited_multispace0(','),
-                    )),
-                    cut_err(fail).context(exp_desc!("one or more parameters")),
-                )),
-                alt((
-                    preceded(
-                        delimited_multispace0(','),
-                        cut_err(']')
-                            .context(exp_desc!("an expression"))
-                            .context(exp_char!(']')),
-                    ),
-                    cut_err('|').context(exp_char!(',')).context(exp_char!('|')),
-                )),
-            ),
+            preceded('|', Self::parse_params),
             Expr::require_parse.map(Box::new),
         )
             .context(label!("function declaration"))
@@ -69,7 +74,7 @@ impl Defined {
         // Restore active context to previous one
         input.state.set_active_ctx(current_ctx_idx);
 
-        let (params, body): (Vec<Ident>, Box<Expr>) = parse_result.inspect_err(|_| {
+        let (params, body): (Vec<Param>, Box<Expr>) = parse_result.inspect_err(|_| {
             // Returned backtrack error during parsing
             // The expression might not be a declaration
 
@@ -77,44 +82,204 @@ impl Defined {
             input.state.decrement_avail_ctx_idx();
         })?;
 
-        // Create a context with params as keys and default bindings
+        // Create a context with params as keys, bound to their defaults if any
         let ctx = Context::from_iter(
             Some(current_ctx_idx),
-            params.into_iter().map(|p| (p, Binding::default())),
+            params.iter().map(|(param, _, default)| {
+                (param.node.clone(), default.clone().unwrap_or_default())
+            }),
         );
 
         // Place this context in the state
-        // This context will then be updated during function call evaluation
+        // Calls evaluate it in a new environment binding the arguments
         input.state.place_ctx(ctx_idx, ctx);
 
-        Ok(Self { ctx_idx, body })
+        Ok(Self {
+            ctx_idx,
+            params: params
+                .into_iter()
+                .map(|(param, kind, _)| (param, kind))
+                .collect(),
+            body,
+        })
+    }
+
+    /// Parses the parameters up to the closing `|`, such as `host, port = 443, ...rest|`.
+    fn parse_params(input: &mut StatefulInput) -> ModalResult<Vec<Param>> {
+        let mut rest = false;
+        let mut names = Vec::new();
+
+        let params: Vec<_> = separated(
+            0..,
+            |input: &mut StatefulInput| {
+                // The rest parameter must be the last one
+                if rest {
+                    return cut_err(fail).context(exp_char!('|')).parse_next(input);
+                }
+
+                comment::skip(input)?;
+                let start = input.checkpoint();
+                let param = Self::parse_param(input)?;
+
+                // A name can only be given to one parameter
+                if names.contains(&param.0.node) {
+                    input.reset(&start);
+                    return cut_err(fail)
+                        .context(exp_desc!("a distinct parameter name"))
+                        .parse_next(input);
+                }
+                names.push(param.0.node.clone());
+
+                comment::skip(input)?;
+                rest = param.1 == ParamKind::Rest;
+                Ok(param)
+            },
+            delimited_multispace0(','),
+        )
+        .parse_next(input)?;
+
+        if params.is_empty() {
+            cut_err(delimited_multispace0('|'))
+                .context(exp_desc!("a parameter"))
+                .context(exp_char!('|'))
+                .parse_next(input)?;
+        } else {
+            // Allow a trailing comma
+            opt(delimited_multispace0(',')).parse_next(input)?;
+            cut_err(delimited_multispace0('|'))
+                .context(exp_char!(','))
+                .context(exp_char!('|'))
+                .parse_next(input)?;
+        }
+
+        Ok(params)
     }
 
-    pub(crate) fn evaluate(self, state: &mut EvalState, args: Vec<Expr>) -> Value {
-        // Check if the number of arguments matches the number of parameters
-        if args.len() != state[self.ctx_idx].len() {
-            return Value::Null;
+    /// Parses a parameter, with a default value or as rest parameter.
+    fn parse_param(input: &mut StatefulInput) -> ModalResult<Param> {
+        if opt("...").parse_next(input)?.is_some() {
+            let param = cut_err(located(Ident::parse_ident))
+                .context(exp_desc!("name of the rest parameter"))
+                .parse_next(input)?;
+            input.state.record_ident(&param, NameKind::Parameter);
+            return Ok((param, ParamKind::Rest, None));
         }
 
-        // Assign argument expressions to the function's context
-        state[self.ctx_idx].assign_from_iter(args);
+        let param = located(Ident::parse_ident).parse_next(input)?;
+        input.state.record_ident(&param, NameKind::Parameter);
 
-        // Save the index of the current active ctx
-        let current_ctx_idx = state.active_ctx_idx();
+        // `|` ends the parameters instead of being an operator in the default
+        let default = opt(preceded(
+            delimited_multispace0(terminated('=', not('='))),
+            cut_err(with_bitwise_or(false, Expr::require_parse))
+                .context(exp_desc!("default value")),
+        ))
+        .parse_next(input)?;
 
-        // Set self as the active context
-        state.set_active_ctx(self.ctx_idx);
+        Ok(match default {
+            Some(default) => (param, ParamKind::Optional, Some(default)),
+            None => (param, ParamKind::Required, None),
+        })
+    }
+
+    /// Calls the function declared in the environment `env` with the given arguments.
+    ///
+    /// Positional arguments are bound to the parameters in order, with any left over
+    /// collected by the rest parameter. Named arguments are then bound by name. The
+    /// body is evaluated in a new environment binding the arguments to the parameters.
+    pub(crate) fn evaluate(
+        self,
+        state: &mut EvalState,
+        span: Span,
+        args: Vec<Arg>,
+        named: Vec<NamedArg>,
+        env: Arc<Env>,
+    ) -> EvalResult {
+        let positional = self
+            .params
+            .iter()
+            .filter(|(_, kind)| *kind != ParamKind::Rest)
+            .count();
+        let has_rest = positional < self.params.len();
+
+        if args.len() > positional && !has_rest {
+            return state.raise(
+                RuntimeErrorKind::ArityMismatch {
+                    expected: positional,
+                    found: args.len(),
+                },
+                span,
+            );
+        }
+
+        // The rest parameter is the last one, so it takes all remaining arguments
+        let mut args = args.into_iter().map(|(value, _)| value);
+        let mut values = self
+            .params
+            .iter()
+            .map(|(_, kind)| match kind {
+                ParamKind::Rest => Some(Value::List(args.by_ref().collect())),
+                _ => args.next(),
+            })
+            .collect::<Vec<_>>();
+
+        for (name, value) in named {
+            let idx = self.params.iter().position(|(param, kind)| {
+                *kind != ParamKind::Rest && state.resolve_ident(&param.node) == name.node
+            });
+            match idx {
+                Some(idx) if values[idx].is_none() => values[idx] = Some(value),
+                Some(_) => {
+                    return state.raise(RuntimeErrorKind::DuplicateArgument(name.node), name.span);
+                }
+                None => {
+                    return state.raise(RuntimeErrorKind::UnknownArgument(name.node), name.span);
+                }
+            }
+        }
 
-        // Evaluate the expression in the context of this block
-        let value = self.body.to_owned().evaluate(state);
+        if let Some(((param, _), _)) = self
+            .params
+            .iter()
+            .zip(&values)
+            .find(|((_, kind), value)| *kind == ParamKind::Required && value.is_none())
+        {
+            let name = state.resolve_ident(&param.node).to_string();
+            return state.raise(RuntimeErrorKind::MissingArgument(name), span);
+        }
 
-        // Reset active context
-        state.set_active_ctx(current_ctx_idx);
+        // Bind the arguments to the parameters in a new environment for this call
+        // Optional parameters without an argument evaluate their default in it
+        let args = self
+            .params
+            .iter()
+            .zip(values)
+            .filter_map(|((param, _), value)| Some((param.node.clone(), value?)));
+        let env = state.new_env(self.ctx_idx, env, args);
 
-        // Reset the context expressions to Null for future calls
-        state[self.ctx_idx].reassign_default_expr();
+        // Evaluate the body in the environment of this call
+        // Its errors are located in its own source, which may be another module's
+        state.with_call(span, |state| {
+            state.with_env(env, |state| {
+                self.body
+                    .evaluate(state)
+                    .map_err(|err| err.locate(state.ctx_state().source()))
+            })
+        })
+    }
 
-        value
+    /// Returns the default values and the body, in source order.
+    pub(crate) fn children<'a>(&'a self, ctx_state: &'a CtxState) -> Vec<&'a Expr> {
+        let ctx = &ctx_state[self.ctx_idx];
+        self.params
+            .iter()
+            .filter(|(_, kind)| *kind == ParamKind::Optional)
+            .filter_map(|(param, _)| match &ctx[&param.node] {
+                Binding::Expr(expr) => Some(expr),
+                Binding::Cached(_) => None,
+            })
+            .chain([self.body.as_ref()])
+            .collect()
     }
 
     pub(crate) fn format<W: std::fmt::Write>(
@@ -124,10 +289,20 @@ impl Defined {
     ) -> std::fmt::Result {
         write!(writer, "|")?;
 
-        let mut params_iter = state[self.ctx_idx].keys().peekable();
+        let mut params_iter = self.params.iter().peekable();
 
-        while let Some(param) = params_iter.next() {
-            param.format(writer, state)?;
+        while let Some((param, kind)) = params_iter.next() {
+            if *kind == ParamKind::Rest {
+                write!(writer, "...")?;
+            }
+            comment::format_node(writer, state, param.span, |writer| {
+                param.node.format(writer, state)
+            })?;
+
+            if *kind == ParamKind::Optional {
+                write!(writer, "{}", if state.pretty() { " = " } else { "=" })?;
+                state[self.ctx_idx][&param.node].format(writer, state)?;
+            }
 
             if params_iter.peek().is_some() {
                 write!(writer, ",")?;
@@ -136,6 +311,10 @@ impl Defined {
                 }
             }
         }
+
+        // Comments before the closing `|`
+        let end = comment::token_before(state, self.body.span().start());
+        comment::format_within(writer, state, end, !self.params.is_empty())?;
         write!(writer, "|")?;
 
         if state.pretty() {
@@ -2,32 +2,49 @@ use winnow::{ModalResult, Parser};
 
 use crate::{
     StatefulInput,
-    expr::Expr,
-    function::defined::Defined,
-    state::{EvalState, FmtState},
+    error::EvalResult,
+    expr::{Expr, ExprKind},
+    function::{closure::Closure, defined::Defined, native::Native},
+    span::{Span, Spanned},
+    state::{CtxState, EvalState, FmtState},
+    utils::spanned,
     value::Value,
 };
 
 pub(crate) mod builtin;
+pub(crate) mod closure;
 pub(crate) mod defined;
+pub(crate) mod native;
 
-/// Function expression (declared or built-in).
+/// Evaluated argument along with the span of its expression.
+pub(crate) type Arg = (Value, Span);
+
+/// Evaluated argument passed by name, along with the span of the name.
+pub(crate) type NamedArg = (Spanned<String>, Value);
+
+/// Function expression (declared, built-in or registered by the host).
 #[derive(Debug, Clone)]
 pub enum Fn {
     Defined(Defined),
-    BuiltIn(fn(&mut EvalState, Vec<Expr>) -> Value),
+    BuiltIn(fn(&mut EvalState, Span, Vec<Arg>) -> EvalResult),
+    Native(Native),
 }
 
 impl Fn {
     pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-        Defined::parse
-            .map(Self::Defined)
-            .map(Expr::Fn)
-            .parse_next(input)
+        spanned(Defined::parse.map(Self::Defined).map(ExprKind::Fn)).parse_next(input)
+    }
+
+    pub(crate) fn evaluate(self, state: &mut EvalState) -> EvalResult {
+        Ok(Value::Function(Closure::new(self, state)))
     }
 
-    pub(crate) fn evaluate(self, _state: &mut EvalState) -> Value {
-        Value::Null
+    /// Returns the default values and the body of a declared function.
+    pub(crate) fn children<'a>(&'a self, ctx_state: &'a CtxState) -> Vec<&'a Expr> {
+        match self {
+            Fn::Defined(defined) => defined.children(ctx_state),
+            Fn::BuiltIn(_) | Fn::Native(_) => Vec::new(),
+        }
     }
 
     pub(crate) fn format<W: std::fmt::Write>(
@@ -38,6 +55,7 @@ impl Fn {
         match self {
             Fn::Defined(declaration) => declaration.format(writer, state),
             Fn::BuiltIn(_) => write!(writer, "<built-in function>"),
+            Fn::Native(_) => write!(writer, "<native function>"),
         }
     }
 }
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use crate::{
+    error::{EvalResult, RuntimeErrorKind},
+    function::Arg,
+    span::Span,
+    state::EvalState,
+    value::Value,
+};
+
+/// Signature of functions registered by the host program.
+pub(crate) type NativeFn =
+    dyn std::ops::Fn(&[Value]) -> Result<Value, RuntimeErrorKind> + Send + Sync;
+
+/// Host-registered function.
+#[derive(Clone)]
+pub struct Native(Arc<NativeFn>);
+
+impl Native {
+    pub(crate) fn new<F>(func: F) -> Self
+    where
+        F: std::ops::Fn(&[Value]) -> Result<Value, RuntimeErrorKind> + Send + Sync + 'static,
+    {
+        Self(Arc::new(func))
+    }
+
+    pub(crate) fn evaluate(&self, state: &mut EvalState, span: Span, args: Vec<Arg>) -> EvalResult {
+        let args = args.into_iter().map(|(value, _)| value).collect::<Vec<_>>();
+
+        // Errors reported by the host point at the whole call
+        (self.0)(&args).or_else(|kind| state.raise(kind, span))
+    }
+}
+
+impl std::fmt::Debug for Native {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Native").finish_non_exhaustive()
+    }
+}
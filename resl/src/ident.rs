@@ -4,9 +4,12 @@ use winnow::{ModalResult, Parser};
 use crate::{
     StatefulInput,
     binding::Binding,
-    expr::Expr,
+    error::{EvalResult, RuntimeErrorKind},
+    expr::{Expr, ExprKind},
+    span::Span,
     state::{EvalState, FmtState, Interner},
     string,
+    utils::spanned,
     value::Value,
 };
 
@@ -17,55 +20,67 @@ pub struct Ident(SymbolU32);
 impl Ident {
     pub(crate) fn parse_ident(input: &mut StatefulInput) -> ModalResult<Self> {
         let ident = string::parse_plain
-            .verify(|s| !["true", "false", "null"].contains(&s))
+            // A leading `-` is a negation, not part of the name
+            .verify(|s: &str| !["true", "false", "null"].contains(&s) && !s.starts_with('-'))
             .parse_next(input)?;
 
         Ok(Ident(input.state.get_interned(ident)))
     }
 
     pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-        Self::parse_ident.map(Expr::Ident).parse_next(input)
+        spanned(Self::parse_ident.map(ExprKind::Ident)).parse_next(input)
     }
 
-    pub(crate) fn evaluate<'a>(self, state: &'a mut EvalState) -> Option<&'a Value> {
-        // Save current context index to restore later
-        let current_ctx_idx = state.active_ctx_idx();
-
-        // Find the context index containing the identifier
-        // This will start from current context upto parent contexts
-        let ctx_idx = state.find_ctx_with_ident(&self)?;
+    pub(crate) fn evaluate(self, state: &mut EvalState, span: Span) -> EvalResult {
+        // Find the environment binding the identifier
+        // This will start from the active environment upto parent environments
+        let Some(env) = state.find_env_with_ident(&self) else {
+            // `inf` and `nan` are floats, unless bound to something else
+            let name = match state.resolve_ident(&self) {
+                "inf" => return Ok(Value::Float(f64::INFINITY)),
+                "nan" => return Ok(Value::Float(f64::NAN)),
+                name => name.to_string(),
+            };
+            return state.raise(RuntimeErrorKind::UnknownIdentifier(name), span);
+        };
 
-        // Set the context index containing the identifier as active
-        // This ensures that any nested lookups have this context as their parent
-        state.set_active_ctx(ctx_idx);
+        // Parameters and bindings evaluated before have a value in the environment
+        if let Some(value) = env.get(&self) {
+            return Ok(value);
+        }
 
         // Initiate the lookup for the identifier
-        // This prevents infinite recursion for cyclic dependencies (Context Sensitive)
-        if !state[ctx_idx].initiate_lookup(&self) {
-            return None;
+        // This prevents infinite recursion for cyclic dependencies (Environment Sensitive)
+        if !env.initiate_lookup(&self) {
+            let name = state.resolve_ident(&self).to_string();
+            return state.raise(RuntimeErrorKind::CyclicReference(name), span);
         }
 
         // Get the expression or cached value for the identifier
-        if let Some(Binding::Expr(expr)) = state[ctx_idx].get(&self) {
-            let cacheable = expr.should_be_cached();
-
-            let value = expr.to_owned().evaluate(state);
-
-            if cacheable {
-                state[ctx_idx].cache(&self, value);
-            };
+        let result = match state[env.ctx_idx()].get(&self).cloned() {
+            Some(Binding::Expr(expr)) => {
+                // Evaluate in the environment binding the identifier
+                // This ensures that any nested lookups start from that environment
+                let result = state.with_env(env.clone(), |state| expr.evaluate(state));
+
+                if let Ok(value) = &result {
+                    // Closures may keep this environment alive, until the evaluation ends
+                    if value.contains_function() {
+                        state.closure_cache().record(&env, &self);
+                    }
+                    env.cache(&self, value.to_owned());
+                };
+
+                result
+            }
+            Some(Binding::Cached(value)) => Ok(value),
+            None => unreachable!("Context is ensured to contain the identifier"),
         };
 
         // Conclude the lookup for the identifier
-        state[ctx_idx].conclude_lookup(&self);
+        env.conclude_lookup(&self);
 
-        // Restore the previous active context index
-        state.set_active_ctx(current_ctx_idx);
-
-        match state[ctx_idx].get(&self) {
-            Some(Binding::Cached(value)) => Some(value),
-            _ => None,
-        }
+        result
     }
 
     pub(crate) fn format<W: std::fmt::Write>(
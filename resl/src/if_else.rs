@@ -5,9 +5,11 @@ use winnow::{
 
 use crate::{
     StatefulInput,
-    expr::Expr,
+    error::{EvalResult, RuntimeErrorKind},
+    expr::{Expr, ExprKind},
     macros::{exp_char, label},
     state::{EvalState, FmtState},
+    utils::{spanned, with_bitwise_or},
     value::Value,
 };
 
@@ -21,35 +23,51 @@ pub struct IfElse {
 
 impl IfElse {
     pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-        separated_pair(
-            preceded('?', Expr::require_parse.map(Box::new)),
-            cut_err(':').context(exp_char!(':')),
+        spanned(
             separated_pair(
-                Expr::require_parse.map(Box::new),
-                cut_err('|').context(exp_char!('|')),
-                Expr::require_parse.map(Box::new),
-            ),
+                preceded('?', Expr::require_parse.map(Box::new)),
+                cut_err(':').context(exp_char!(':')),
+                separated_pair(
+                    // `|` separates the branches rather than being bitwise or
+                    with_bitwise_or(false, Expr::require_parse.map(Box::new)),
+                    cut_err('|').context(exp_char!('|')),
+                    Expr::require_parse.map(Box::new),
+                ),
+            )
+            .context(label!("conditional expression"))
+            .map(|(if_expr, (then_expr, else_expr))| Self {
+                if_expr,
+                then_expr,
+                else_expr,
+            })
+            .map(ExprKind::IfElse),
         )
-        .context(label!("conditional expression"))
-        .map(|(if_expr, (then_expr, else_expr))| Self {
-            if_expr,
-            then_expr,
-            else_expr,
-        })
-        .map(Expr::IfElse)
         .parse_next(input)
     }
 
-    pub(crate) fn evaluate(self, state: &mut EvalState) -> Value {
-        match self.if_expr.evaluate(state) {
+    pub(crate) fn evaluate(self, state: &mut EvalState) -> EvalResult {
+        let if_span = self.if_expr.span();
+
+        match self.if_expr.evaluate(state)? {
             Value::Boolean(bool) => match bool {
                 true => self.then_expr.evaluate(state),
                 false => self.else_expr.evaluate(state),
             },
-            _ => Value::Null,
+            value => state.raise(
+                RuntimeErrorKind::TypeMismatch {
+                    expected: "boolean",
+                    found: value.type_name(),
+                },
+                if_span,
+            ),
         }
     }
 
+    /// Returns the condition and both branches.
+    pub(crate) fn children(&self) -> Vec<&Expr> {
+        vec![&self.if_expr, &self.then_expr, &self.else_expr]
+    }
+
     pub(crate) fn format<W: std::fmt::Write>(
         &self,
         writer: &mut W,
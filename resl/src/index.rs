@@ -5,19 +5,21 @@ use winnow::{
 
 use crate::{
     StatefulInput,
-    expr::Expr,
-    ident::Ident,
+    error::{EvalResult, RuntimeErrorKind},
+    expr::{Expr, ExprKind},
+    infix::InfixOp,
     macros::{exp_char, exp_desc, label},
+    span::Spanned,
     state::{EvalState, FmtState},
-    utils::delimited_multispace0,
+    utils::{delimited_multispace0, located, with_bitwise_or},
     value::Value,
 };
 
 /// Index operation for element access.
 #[derive(Debug, Clone)]
 pub struct Index {
-    base: Ident,
-    indices: Vec<IndexType>,
+    base: Box<Expr>,
+    indices: Vec<Spanned<IndexType>>,
 }
 
 /// Type of indexing operation.
@@ -42,167 +44,222 @@ pub(crate) enum RangeBounds {
 
 impl Index {
     pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-        (
-            Ident::parse_ident,
-            repeat(
-                1..,
-                preceded(
-                    '[',
+        let base = InfixOp::parse_primary(input)?;
+
+        let indices: Vec<Spanned<IndexType>> =
+            with_bitwise_or(true, repeat(0.., located(Self::parse_index)))
+                .context(label!("index expression"))
+                .parse_next(input)?;
+
+        let Some(last) = indices.last() else {
+            return Ok(base);
+        };
+
+        let span = base.span().to(last.span);
+        Ok(Expr::new(
+            ExprKind::Index(Self {
+                base: Box::new(base),
+                indices,
+            }),
+            span,
+        ))
+    }
+
+    /// Parses a single `[...]` index or range following the base.
+    fn parse_index(input: &mut StatefulInput) -> ModalResult<IndexType> {
+        preceded(
+            '[',
+            alt((
+                // For cases:
+                // - `x[0]`
+                // - `x[0:]`
+                // - `x[0:1]`
+                (
+                    Expr::parse,
                     alt((
-                        // For cases:
-                        // - `x[0]`
-                        // - `x[0:]`
-                        // - `x[0:1]`
-                        (
-                            Expr::parse,
-                            alt((
-                                delimited(
-                                    ':',
-                                    delimited_multispace0(
-                                        // Optional end expression for case:
-                                        // - `x[0:1]`
-                                        // - `x[0:]`
-                                        opt(Expr::parse),
-                                    ),
-                                    // Require closing ']'
-                                    cut_err(']')
-                                        .context(exp_desc!("an expression"))
-                                        .context(exp_char!(']')),
-                                )
-                                .map(Some),
-                                // Accept immediate closing ']' for case:
-                                // - `x[0]
-                                ']'.value(None),
-                                // Fail otherwise
-                                cut_err(fail)
-                                    .context(exp_char!(':'))
-                                    .context(exp_char!(']')),
-                            )),
-                        )
-                            .map(
-                                |(start, end): (Expr, Option<Option<Expr>>)| match end {
-                                    Some(end) => match end {
-                                        Some(end) => {
-                                            IndexType::Range(RangeBounds::FromTo(start, end))
-                                        }
-                                        None => IndexType::Range(RangeBounds::StartingFrom(start)),
-                                    },
-                                    None => IndexType::Single(start),
-                                },
-                            ),
-                        // For case:
-                        // - `x[:5]`
                         delimited(
                             ':',
-                            // Required expression for range end
-                            Expr::require_parse,
-                            // Peek for closing ']', otherwise fail
-                            cut_err(']').context(exp_char!(']')),
+                            delimited_multispace0(
+                                // Optional end expression for case:
+                                // - `x[0:1]`
+                                // - `x[0:]`
+                                opt(Expr::parse),
+                            ),
+                            // Require closing ']'
+                            cut_err(']')
+                                .context(exp_desc!("an expression"))
+                                .context(exp_char!(']')),
                         )
-                        .map(|end| IndexType::Range(RangeBounds::EndingAt(end))),
-                        // For case:
-                        // - `x[]`
+                        .map(Some),
+                        // Accept immediate closing ']' for case:
+                        // - `x[0]
+                        ']'.value(None),
+                        // Fail otherwise
                         cut_err(fail)
-                            .context(exp_desc!("An expression"))
-                            .context(exp_char!(':')),
+                            .context(exp_char!(':'))
+                            .context(exp_char!(']')),
                     )),
-                ),
-            ),
+                )
+                    .map(|(start, end): (Expr, Option<Option<Expr>>)| match end {
+                        Some(end) => match end {
+                            Some(end) => IndexType::Range(RangeBounds::FromTo(start, end)),
+                            None => IndexType::Range(RangeBounds::StartingFrom(start)),
+                        },
+                        None => IndexType::Single(start),
+                    }),
+                // For case:
+                // - `x[:5]`
+                delimited(
+                    ':',
+                    // Required expression for range end
+                    Expr::require_parse,
+                    // Peek for closing ']', otherwise fail
+                    cut_err(']').context(exp_char!(']')),
+                )
+                .map(|end| IndexType::Range(RangeBounds::EndingAt(end))),
+                // For case:
+                // - `x[]`
+                cut_err(fail)
+                    .context(exp_desc!("An expression"))
+                    .context(exp_char!(':')),
+            )),
         )
-            .context(label!("index expression"))
-            .map(|(ident, indices): (Ident, Vec<IndexType>)| Self {
-                base: ident,
-                indices,
-            })
-            .map(Expr::Index)
-            .parse_next(input)
+        .parse_next(input)
     }
 
-    pub(crate) fn evaluate(self, state: &mut EvalState) -> Value {
-        let Some(base_value) = self.base.evaluate(state) else {
-            return Value::Null;
-        };
+    pub(crate) fn evaluate(self, state: &mut EvalState) -> EvalResult {
+        // Span of the expression indexed by the current segment
+        let mut base_span = self.base.span();
 
-        let mut base_value = if matches!(base_value, Value::Map(_) | Value::List(_)) {
-            base_value.clone()
-        } else {
-            return Value::Null;
-        };
+        let mut base_value = self.base.evaluate(state)?;
 
-        for index in self.indices {
-            match index {
+        for Spanned { node: index, span } in self.indices {
+            base_value = match index {
                 IndexType::Single(index_expr) => {
-                    let index_value = index_expr.evaluate(state);
-                    match index_value {
+                    let index_span = index_expr.span();
+                    let index_value = index_expr.evaluate(state)?;
+
+                    match (base_value, index_value) {
                         // If the index is a string, try to get from map
-                        Value::String(string) => {
-                            if let Value::Map(map) = base_value {
-                                base_value = map.get(&string).cloned().unwrap_or_default();
-                            } else {
-                                return Value::Null;
-                            }
+                        (Value::Map(map), Value::String(string)) => {
+                            map.get(&string).cloned().unwrap_or_default()
                         }
-                        // If the index is a non-negative integer, try to get from list
-                        Value::Integer(int) if int >= 0 => {
-                            if let Value::List(list) = base_value {
-                                base_value = list.get(int as usize).cloned().unwrap_or_default();
-                            } else {
-                                return Value::Null;
+                        // If the index is an integer, try to get from list
+                        (Value::List(list), Value::Integer(int)) => {
+                            match usize::try_from(int).ok().and_then(|idx| list.get(idx)) {
+                                Some(value) => value.to_owned(),
+                                None => {
+                                    return state.raise(
+                                        RuntimeErrorKind::IndexOutOfBounds {
+                                            index: int,
+                                            len: list.len(),
+                                        },
+                                        index_span,
+                                    );
+                                }
                             }
                         }
-                        _ => {
-                            return Value::Null;
+                        (Value::Map(_), index_value) => {
+                            return state.raise(
+                                RuntimeErrorKind::TypeMismatch {
+                                    expected: "string",
+                                    found: index_value.type_name(),
+                                },
+                                index_span,
+                            );
+                        }
+                        (Value::List(_), index_value) => {
+                            return state.raise(
+                                RuntimeErrorKind::TypeMismatch {
+                                    expected: "integer",
+                                    found: index_value.type_name(),
+                                },
+                                index_span,
+                            );
+                        }
+                        (base_value, _) => {
+                            return state.raise(
+                                RuntimeErrorKind::TypeMismatch {
+                                    expected: "list or map",
+                                    found: base_value.type_name(),
+                                },
+                                base_span,
+                            );
                         }
                     }
                 }
                 IndexType::Range(range_bounds) => {
-                    if let Value::List(list) = base_value {
-                        let range = match range_bounds {
-                            RangeBounds::StartingFrom(index) => {
-                                let Some(start) = expr_to_usize(index, state) else {
-                                    return Value::Null;
-                                };
-                                if start >= list.len() {
-                                    return Value::Null;
-                                }
-                                start..list.len()
-                            }
-                            RangeBounds::EndingAt(end) => {
-                                let Some(end) = expr_to_usize(end, state) else {
-                                    return Value::Null;
-                                };
-                                if end > list.len() {
-                                    return Value::Null;
-                                }
-                                0..end
-                            }
-                            RangeBounds::FromTo(start, end) => {
-                                let Some(start) = expr_to_usize(start, state) else {
-                                    return Value::Null;
-                                };
-                                let Some(end) = expr_to_usize(end, state) else {
-                                    return Value::Null;
-                                };
-
-                                if start > end || start >= list.len() || end > list.len() {
-                                    return Value::Null;
-                                }
+                    let Value::List(list) = base_value else {
+                        return state.raise(
+                            RuntimeErrorKind::TypeMismatch {
+                                expected: "list",
+                                found: base_value.type_name(),
+                            },
+                            base_span,
+                        );
+                    };
+
+                    let len = list.len();
 
-                                start..end
+                    let range = match range_bounds {
+                        RangeBounds::StartingFrom(start) => {
+                            let Some(start) = evaluate_bound(start, len, false, state)? else {
+                                return Ok(Value::Null);
+                            };
+                            start..len
+                        }
+                        RangeBounds::EndingAt(end) => {
+                            let Some(end) = evaluate_bound(end, len, true, state)? else {
+                                return Ok(Value::Null);
+                            };
+                            0..end
+                        }
+                        RangeBounds::FromTo(start, end) => {
+                            let Some(start) = evaluate_bound(start, len, false, state)? else {
+                                return Ok(Value::Null);
+                            };
+                            let end_span = end.span();
+                            let Some(end) = evaluate_bound(end, len, true, state)? else {
+                                return Ok(Value::Null);
+                            };
+
+                            if start > end {
+                                return state.raise(
+                                    RuntimeErrorKind::IndexOutOfBounds {
+                                        index: end as i64,
+                                        len,
+                                    },
+                                    end_span,
+                                );
                             }
-                        };
 
-                        base_value = list
-                            .get(range)
-                            .map(|slice| slice.to_vec())
-                            .map(Value::List)
-                            .unwrap_or_default();
-                    }
+                            start..end
+                        }
+                    };
+
+                    Value::List(list[range].to_vec())
                 }
-            }
+            };
+
+            base_span = base_span.to(span);
         }
 
-        base_value
+        Ok(base_value)
+    }
+
+    /// Returns the base and the index expressions, in source order.
+    pub(crate) fn children(&self) -> Vec<&Expr> {
+        let mut children = vec![self.base.as_ref()];
+        for index in &self.indices {
+            match &index.node {
+                IndexType::Single(expr)
+                | IndexType::Range(RangeBounds::StartingFrom(expr) | RangeBounds::EndingAt(expr)) => {
+                    children.push(expr)
+                }
+                IndexType::Range(RangeBounds::FromTo(start, end)) => children.extend([start, end]),
+            }
+        }
+        children
     }
 
     pub(crate) fn format<W: std::fmt::Write>(
@@ -210,10 +267,17 @@ impl Index {
         writer: &mut W,
         state: FmtState,
     ) -> std::fmt::Result {
-        self.base.format(writer, state)?;
+        // A prefix operation would otherwise apply to the indexed value
+        if matches!(self.base.kind(), ExprKind::PrefixOp(_)) {
+            write!(writer, "(")?;
+            self.base.format(writer, state)?;
+            write!(writer, ")")?;
+        } else {
+            self.base.format(writer, state)?;
+        }
 
         for index in self.indices.iter() {
-            match index {
+            match &index.node {
                 IndexType::Single(index) => {
                     write!(writer, "[")?;
                     index.format(writer, state)?;
@@ -245,9 +309,37 @@ impl Index {
     }
 }
 
-fn expr_to_usize(expr: Expr, state: &mut EvalState) -> Option<usize> {
-    match expr.evaluate(state) {
-        Value::Integer(int) if int >= 0 => Some(int as usize),
-        _ => None,
+/// Evaluates a range bound and checks it against the length of the list.
+///
+/// The start of a range must lie within the list, whereas its end may be equal to
+/// the length of the list. Returns `None` if the bound is invalid in lenient mode.
+fn evaluate_bound(
+    expr: Expr,
+    len: usize,
+    is_end: bool,
+    state: &mut EvalState,
+) -> EvalResult<Option<usize>> {
+    let span = expr.span();
+
+    let int = match expr.evaluate(state)? {
+        Value::Integer(int) => int,
+        value => {
+            return state
+                .raise(
+                    RuntimeErrorKind::TypeMismatch {
+                        expected: "integer",
+                        found: value.type_name(),
+                    },
+                    span,
+                )
+                .map(|_| None);
+        }
+    };
+
+    match usize::try_from(int) {
+        Ok(bound) if bound < len || (is_end && bound == len) => Ok(Some(bound)),
+        _ => state
+            .raise(RuntimeErrorKind::IndexOutOfBounds { index: int, len }, span)
+            .map(|_| None),
     }
 }
@@ -1,25 +1,38 @@
+use std::cmp::Ordering;
+
 use winnow::{
     ModalResult, Parser,
-    combinator::{alt, cut_err, delimited, opt},
+    combinator::{alt, cut_err, delimited, fail, opt, peek},
+    stream::Stream,
 };
 
 use crate::{
     StatefulInput,
     block::Block,
     boolean,
-    expr::Expr,
+    error::{EvalResult, RuntimeErrorKind},
+    expr::{Expr, ExprKind},
     fn_call::FnCall,
+    for_each::ForEach,
     ident::Ident,
     index::Index,
+    list,
     macros::{exp_desc, exp_str, label},
-    number,
+    map,
+    match_expr::Match,
+    null, number,
     prefix::PrefixOp,
+    span::Span,
     state::{EvalState, FmtState},
     string,
-    utils::delimited_multispace0,
+    utils::{delimited_multispace0, with_bitwise_or},
     value::Value,
 };
 
+/// Maximum number of items a range can produce, so that a mistyped bound fails
+/// instead of exhausting memory.
+const MAX_RANGE_LEN: usize = 1_000_000;
+
 /// Binary infix operation.
 #[derive(Debug, Clone)]
 pub struct InfixOp {
@@ -33,8 +46,12 @@ pub struct InfixOp {
 #[derive(Debug, Clone, Copy)]
 enum Op {
     Arithmetic(ArithmeticOp),
+    Bitwise(BitwiseOp),
     Logic(LogicOp),
     Comparison(ComparisonOp),
+    /// Null-coalescing operator `??`.
+    Coalesce,
+    Range(RangeOp),
 }
 
 /// Arithmetic operators.
@@ -44,6 +61,19 @@ enum ArithmeticOp {
     Sub,
     Mul,
     Div,
+    IntDiv,
+    Rem,
+    Pow,
+}
+
+/// Bitwise operators on integers.
+#[derive(Debug, Clone, Copy)]
+enum BitwiseOp {
+    And,
+    Or,
+    Xor,
+    Shl,
+    Shr,
 }
 
 /// Logical operators.
@@ -53,6 +83,15 @@ enum LogicOp {
     Or,
 }
 
+/// Range operators producing a list of integers.
+#[derive(Debug, Clone, Copy)]
+enum RangeOp {
+    /// `start..end`, which excludes the end
+    Exclusive,
+    /// `start..=end`, which includes the end
+    Inclusive,
+}
+
 /// Comparison operators.
 #[derive(Debug, Clone, Copy)]
 enum ComparisonOp {
@@ -66,20 +105,36 @@ enum ComparisonOp {
 
 impl InfixOp {
     // Parse valid operands for infix expressions, avoiding infinite recursion
-    fn parse_operand(input: &mut StatefulInput) -> ModalResult<Expr> {
+    pub(crate) fn parse_operand(input: &mut StatefulInput) -> ModalResult<Expr> {
+        alt((
+            // Parses a primary expression, followed by any indices
+            Index::parse,
+            PrefixOp::parse,
+        ))
+        .parse_next(input)
+    }
+
+    /// Parses an operand that can be indexed or iterated without parentheses.
+    pub(crate) fn parse_primary(input: &mut StatefulInput) -> ModalResult<Expr> {
         alt((
             string::parse,
             number::parse,
-            // Index and FnCall have to be parsed before Ident
-            // because they have ident as their first parser
-            Index::parse,
+            // Match has to be parsed before Ident
+            // because `match` is also a valid name
+            Match::parse,
+            // FnCall has to be parsed before Ident
+            // because it has ident as its first parser
             FnCall::parse,
             Ident::parse,
-            // Boolean must be parsed after Ident
-            // Ident already discovers true/false as identifiers
+            // Boolean and null must be parsed after Ident
+            // Ident already discovers true/false/null as identifiers
             boolean::parse,
+            null::parse,
+            // Map must be parsed before List
+            // An empty `[]` is a list
+            map::parse,
+            list::parse,
             Block::parse,
-            PrefixOp::parse,
             InfixOp::parse_parenthesized,
         ))
         .parse_next(input)
@@ -89,154 +144,308 @@ impl InfixOp {
         alt((
             "+".value(Op::Arithmetic(ArithmeticOp::Add)),
             "-".value(Op::Arithmetic(ArithmeticOp::Sub)),
+            "**".value(Op::Arithmetic(ArithmeticOp::Pow)),
             "*".value(Op::Arithmetic(ArithmeticOp::Mul)),
             "/".value(Op::Arithmetic(ArithmeticOp::Div)),
+            "~/".value(Op::Arithmetic(ArithmeticOp::IntDiv)),
+            "%".value(Op::Arithmetic(ArithmeticOp::Rem)),
+            "??".value(Op::Coalesce),
             "&&".value(Op::Logic(LogicOp::And)),
             "||".value(Op::Logic(LogicOp::Or)),
-            "==".value(Op::Comparison(ComparisonOp::Eq)),
-            "!=".value(Op::Comparison(ComparisonOp::NotEq)),
-            ">=".value(Op::Comparison(ComparisonOp::GtOrEq)),
-            "<=".value(Op::Comparison(ComparisonOp::LtOrEq)),
-            ">".value(Op::Comparison(ComparisonOp::Gt)),
-            "<".value(Op::Comparison(ComparisonOp::Lt)),
+            "&".value(Op::Bitwise(BitwiseOp::And)),
+            Self::parse_bitwise_or,
+            "^".value(Op::Bitwise(BitwiseOp::Xor)),
+            "<<".value(Op::Bitwise(BitwiseOp::Shl)),
+            ">>".value(Op::Bitwise(BitwiseOp::Shr)),
+            alt((
+                "==".value(Op::Comparison(ComparisonOp::Eq)),
+                "!=".value(Op::Comparison(ComparisonOp::NotEq)),
+                ">=".value(Op::Comparison(ComparisonOp::GtOrEq)),
+                "<=".value(Op::Comparison(ComparisonOp::LtOrEq)),
+                ">".value(Op::Comparison(ComparisonOp::Gt)),
+                "<".value(Op::Comparison(ComparisonOp::Lt)),
+            )),
+            alt((
+                "..=".value(Op::Range(RangeOp::Inclusive)),
+                "..".value(Op::Range(RangeOp::Exclusive)),
+            )),
         ))
         .context(exp_str!("+"))
         .context(exp_str!("-"))
+        .context(exp_str!("**"))
         .context(exp_str!("*"))
         .context(exp_str!("/"))
+        .context(exp_str!("~/"))
+        .context(exp_str!("%"))
+        .context(exp_str!("??"))
         .context(exp_str!("&&"))
         .context(exp_str!("||"))
+        .context(exp_str!("&"))
+        .context(exp_str!("|"))
+        .context(exp_str!("^"))
+        .context(exp_str!("<<"))
+        .context(exp_str!(">>"))
         .context(exp_str!("=="))
         .context(exp_str!("!="))
         .context(exp_str!(">="))
         .context(exp_str!("<="))
         .context(exp_str!(">"))
         .context(exp_str!("<"))
+        .context(exp_str!(".."))
+        .context(exp_str!("..="))
         .parse_next(input)
     }
 
+    /// Parses `|`, unless it separates the branches of an enclosing conditional.
+    fn parse_bitwise_or(input: &mut StatefulInput) -> ModalResult<Op> {
+        if !input.state.bitwise_or() {
+            return fail.parse_next(input);
+        }
+        "|".value(Op::Bitwise(BitwiseOp::Or)).parse_next(input)
+    }
+
     pub(crate) fn parse_parenthesized(input: &mut StatefulInput) -> ModalResult<Expr> {
         delimited(
             '(',
-            (
-                // Right operand can be another infix expression
-                cut_err(Self::parse_operand.map(Box::new)).context(exp_desc!("operand")),
-                delimited_multispace0(cut_err(Self::parse_operator)),
-                // Right operand can be another infix expression
-                cut_err(Self::parse.map(Box::new)).context(exp_desc!("operand")),
+            with_bitwise_or(
+                true,
+                delimited_multispace0(cut_err(Self::parse).context(exp_desc!("operand"))),
             ),
-            ')',
+            cut_err(')')
+                .context(exp_str!(")"))
+                .context(label!("infix expression")),
         )
-        .context(label!("infix expression"))
-        .map(|(lhs, op, rhs)| InfixOp {
-            lhs,
-            op,
-            rhs,
-            parenthesized: true,
+        .with_span()
+        .map(|(expr, range)| {
+            let span = expr.span();
+            match expr.into_kind() {
+                // Remember the parentheses so that formatting keeps them
+                ExprKind::InfixOp(infix_op) => Expr::new(
+                    ExprKind::InfixOp(InfixOp {
+                        parenthesized: true,
+                        ..infix_op
+                    }),
+                    range.into(),
+                ),
+                // Parentheses around a single operand don't change its meaning
+                kind => Expr::new(kind, span),
+            }
         })
-        .map(Expr::InfixOp)
         .parse_next(input)
     }
 
     pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-        alt((
-            // Non-parenthesized operation or single operand
-            (
-                Self::parse_operand,
-                // Optional operator and right operand
-                opt((
-                    delimited_multispace0(Self::parse_operator),
-                    // Right operand can be another infix expression
-                    cut_err(Self::parse).context(exp_desc!("operand")),
-                ))
-                .context(label!("infix expression")),
-            )
-                .map(|(lhs, rest)| match rest {
-                    Some((op, rhs)) => Expr::InfixOp(InfixOp {
-                        lhs: Box::new(lhs),
-                        op,
-                        rhs: Box::new(rhs),
-                        parenthesized: false,
-                    }),
-                    None => lhs,
+        Self::parse_with_precedence(input, 0)
+    }
+
+    /// Parses an operand raised to powers, as in `2 ** 3`, which bind tighter than
+    /// the prefix operator before it.
+    pub(crate) fn parse_power(input: &mut StatefulInput) -> ModalResult<Expr> {
+        Self::parse_with_precedence(input, Op::Arithmetic(ArithmeticOp::Pow).precedence())
+    }
+
+    fn parse_with_precedence(input: &mut StatefulInput, min_precedence: u8) -> ModalResult<Expr> {
+        let lhs = Self::parse_operand(input)?;
+        Self::parse_chain(input, lhs, min_precedence)
+    }
+
+    /// Parses a chain of operations following the already parsed `lhs`, whose
+    /// operators bind at least as tightly as `min_precedence`. Operators of equal
+    /// precedence are grouped from the left except for the right-associative `**`.
+    ///
+    /// The chain ends before a for-each pattern, so that the whole chain is iterated.
+    pub(crate) fn parse_chain(
+        input: &mut StatefulInput,
+        mut lhs: Expr,
+        min_precedence: u8,
+    ) -> ModalResult<Expr> {
+        loop {
+            if opt(peek(ForEach::parse_pattern))
+                .parse_next(input)?
+                .is_some()
+            {
+                break;
+            }
+
+            let checkpoint = input.checkpoint();
+
+            let Some(op) = opt(delimited_multispace0(Self::parse_operator)).parse_next(input)?
+            else {
+                break;
+            };
+
+            // Leave looser operators to the enclosing chain
+            if op.precedence() < min_precedence {
+                input.reset(&checkpoint);
+                break;
+            }
+
+            let rhs_precedence = match op {
+                Op::Arithmetic(ArithmeticOp::Pow) => op.precedence(),
+                _ => op.precedence() + 1,
+            };
+
+            let rhs = cut_err(|input: &mut StatefulInput| {
+                Self::parse_with_precedence(input, rhs_precedence)
+            })
+            .context(exp_desc!("operand"))
+            .context(label!("infix expression"))
+            .parse_next(input)?;
+
+            let span = lhs.span().to(rhs.span());
+            lhs = Expr::new(
+                ExprKind::InfixOp(InfixOp {
+                    lhs: Box::new(lhs),
+                    op,
+                    rhs: Box::new(rhs),
+                    parenthesized: false,
                 }),
-            // Parenthesized infix operation
-            Self::parse_parenthesized,
-        ))
-        .parse_next(input)
+                span,
+            );
+        }
+
+        Ok(lhs)
+    }
+
+    pub(crate) fn evaluate(self, state: &mut EvalState) -> EvalResult {
+        let lhs_span = self.lhs.span();
+        let rhs_span = self.rhs.span();
+
+        let lhs_value = self.lhs.evaluate(state)?;
+
+        // The right operand of logical operators and `??` is only evaluated when needed
+        match (self.op, lhs_value) {
+            (Op::Logic(LogicOp::And), Value::Boolean(false)) => Ok(Value::Boolean(false)),
+            (Op::Logic(LogicOp::Or), Value::Boolean(true)) => Ok(Value::Boolean(true)),
+            (Op::Logic(_), Value::Boolean(_)) => match self.rhs.evaluate(state)? {
+                Value::Boolean(r_bool) => Ok(Value::Boolean(r_bool)),
+                rhs_value => state.raise(boolean_expected(&rhs_value), rhs_span),
+            },
+            (Op::Logic(_), lhs_value) => state.raise(boolean_expected(&lhs_value), lhs_span),
+            (Op::Coalesce, Value::Null) => self.rhs.evaluate(state),
+            (Op::Coalesce, lhs_value) => Ok(lhs_value),
+            (_, lhs_value) => {
+                let rhs_value = self.rhs.evaluate(state)?;
+                Self::evaluate_eager(self.op, (lhs_value, lhs_span), (rhs_value, rhs_span), state)
+            }
+        }
     }
 
-    pub(crate) fn evaluate(self, state: &mut EvalState) -> Value {
-        let lhs_value = self.lhs.evaluate(state);
-        let rhs_value = self.rhs.evaluate(state);
-
-        match lhs_value {
-            Value::Integer(int_lhs) => match self.op {
-                Op::Arithmetic(math_op) => {
-                    let int_rhs = match rhs_value {
-                        Value::Integer(int) => int,
-                        Value::Float(dec) => dec.round() as i64,
-                        _ => return Value::Null,
+    /// Applies an operator that needs the values of both operands.
+    fn evaluate_eager(
+        op: Op,
+        (lhs_value, lhs_span): (Value, Span),
+        (rhs_value, rhs_span): (Value, Span),
+        state: &mut EvalState,
+    ) -> EvalResult {
+        match op {
+            Op::Arithmetic(math_op) => {
+                let concat = matches!(math_op, ArithmeticOp::Add);
+
+                let result = match (lhs_value, rhs_value) {
+                    // `+` also concatenates strings and lists and merges maps
+                    (Value::String(mut str_lhs), Value::String(str_rhs)) if concat => {
+                        str_lhs.push_str(&str_rhs);
+                        Ok(Value::String(str_lhs))
+                    }
+                    (Value::List(mut list_lhs), Value::List(list_rhs)) if concat => {
+                        list_lhs.extend(list_rhs);
+                        Ok(Value::List(list_lhs))
+                    }
+                    // Entries of the right map replace those of the left map
+                    (Value::Map(mut map_lhs), Value::Map(map_rhs)) if concat => {
+                        map_lhs.extend(map_rhs);
+                        Ok(Value::Map(map_lhs))
+                    }
+                    (
+                        lhs_value @ (Value::String(_) | Value::List(_) | Value::Map(_)),
+                        rhs_value,
+                    ) if concat => {
+                        return state.raise(
+                            RuntimeErrorKind::TypeMismatch {
+                                expected: lhs_value.type_name(),
+                                found: rhs_value.type_name(),
+                            },
+                            rhs_span,
+                        );
+                    }
+                    (Value::Integer(int_lhs), Value::Integer(int_rhs)) => {
+                        handle_integer_ops(math_op, int_lhs, int_rhs)
+                    }
+                    // Mixed operands are promoted to floats
+                    (Value::Integer(int_lhs), Value::Float(dec_rhs)) => {
+                        handle_float_ops(math_op, int_lhs as f64, dec_rhs)
+                    }
+                    (Value::Float(dec_lhs), Value::Integer(int_rhs)) => {
+                        handle_float_ops(math_op, dec_lhs, int_rhs as f64)
+                    }
+                    (Value::Float(dec_lhs), Value::Float(dec_rhs)) => {
+                        handle_float_ops(math_op, dec_lhs, dec_rhs)
+                    }
+                    (Value::Integer(_) | Value::Float(_), rhs_value) => {
+                        return state.raise(number_expected(&rhs_value), rhs_span);
+                    }
+                    (lhs_value, _) => return state.raise(number_expected(&lhs_value), lhs_span),
+                };
+
+                result.or_else(|kind| {
+                    // A zero divisor is the fault of the right operand alone
+                    let span = match kind {
+                        RuntimeErrorKind::DivisionByZero => rhs_span,
+                        _ => lhs_span.to(rhs_span),
                     };
-                    return Value::Integer(handle_math_ops(math_op, int_lhs, int_rhs));
+                    state.raise(kind, span)
+                })
+            }
+            Op::Bitwise(bit_op) => match (lhs_value, rhs_value) {
+                (Value::Integer(int_lhs), Value::Integer(int_rhs)) => {
+                    handle_bitwise_ops(bit_op, int_lhs, int_rhs)
+                        .map(Value::Integer)
+                        .or_else(|kind| state.raise(kind, rhs_span))
                 }
-                Op::Comparison(comp_op) => {
-                    let int_rhs = match rhs_value {
-                        Value::Integer(int) => int,
-                        Value::Float(dec) => dec.round() as i64,
-                        _ => return Value::Null,
-                    };
-                    return match comp_op {
-                        ComparisonOp::Eq => Value::Boolean(int_lhs == int_rhs),
-                        ComparisonOp::NotEq => Value::Boolean(int_lhs != int_rhs),
-                        ComparisonOp::Gt => Value::Boolean(int_lhs > int_rhs),
-                        ComparisonOp::Lt => Value::Boolean(int_lhs < int_rhs),
-                        ComparisonOp::GtOrEq => Value::Boolean(int_lhs >= int_rhs),
-                        ComparisonOp::LtOrEq => Value::Boolean(int_lhs <= int_rhs),
-                    };
+                (Value::Integer(_), rhs_value) => {
+                    state.raise(integer_expected(&rhs_value), rhs_span)
                 }
-                _ => (),
+                (lhs_value, _) => state.raise(integer_expected(&lhs_value), lhs_span),
+            },
+            // Any two values can be tested for equality
+            Op::Comparison(ComparisonOp::Eq) => Ok(Value::Boolean(lhs_value.equals(&rhs_value))),
+            Op::Comparison(ComparisonOp::NotEq) => {
+                Ok(Value::Boolean(!lhs_value.equals(&rhs_value)))
+            }
+            Op::Comparison(comp_op) => match lhs_value.compare(&rhs_value) {
+                Ok(ordering) => Ok(Value::Boolean(handle_comparison_ops(comp_op, ordering))),
+                Err(kind) => state.raise(kind, lhs_span.to(rhs_span)),
             },
-            Value::Float(dec_lhs) => match self.op {
-                Op::Arithmetic(math_op) => {
-                    let dec_rhs = match rhs_value {
-                        Value::Integer(int) => int as f64,
-                        Value::Float(dec) => dec,
-                        _ => return Value::Null,
+            Op::Range(range_op) => match (lhs_value, rhs_value) {
+                (Value::Integer(start), Value::Integer(end)) => {
+                    let len = i128::from(end) - i128::from(start)
+                        + i128::from(matches!(range_op, RangeOp::Inclusive));
+                    if len > MAX_RANGE_LEN as i128 {
+                        return state.raise(
+                            RuntimeErrorKind::RangeTooLarge(MAX_RANGE_LEN),
+                            lhs_span.to(rhs_span),
+                        );
+                    }
+
+                    let range = match range_op {
+                        RangeOp::Exclusive => (start..end).map(Value::Integer).collect(),
+                        RangeOp::Inclusive => (start..=end).map(Value::Integer).collect(),
                     };
-                    return Value::Float(handle_math_ops(math_op, dec_lhs, dec_rhs));
+                    Ok(Value::List(range))
                 }
-                Op::Comparison(comp_op) => {
-                    let r_float = match rhs_value {
-                        Value::Integer(r_int) => r_int as f64,
-                        Value::Float(r_float) => r_float,
-                        _ => return Value::Null,
-                    };
-                    return match comp_op {
-                        ComparisonOp::Eq => Value::Boolean(dec_lhs == r_float),
-                        ComparisonOp::NotEq => Value::Boolean(dec_lhs != r_float),
-                        ComparisonOp::Gt => Value::Boolean(dec_lhs > r_float),
-                        ComparisonOp::Lt => Value::Boolean(dec_lhs < r_float),
-                        ComparisonOp::GtOrEq => Value::Boolean(dec_lhs >= r_float),
-                        ComparisonOp::LtOrEq => Value::Boolean(dec_lhs <= r_float),
-                    };
+                (Value::Integer(_), rhs_value) => {
+                    state.raise(integer_expected(&rhs_value), rhs_span)
                 }
-                _ => {}
+                (lhs_value, _) => state.raise(integer_expected(&lhs_value), lhs_span),
             },
-            Value::Boolean(l_bool) => {
-                if let Op::Logic(logical_op) = self.op {
-                    let r_bool = match rhs_value {
-                        Value::Boolean(r_bool) => r_bool,
-                        _ => return Value::Null,
-                    };
-
-                    return Value::Boolean(handle_logical_ops(logical_op, l_bool, r_bool));
-                }
-            }
-            _ => {}
+            Op::Logic(_) | Op::Coalesce => unreachable!("Evaluated lazily"),
         }
+    }
 
-        Value::Null
+    /// Returns the operands.
+    pub(crate) fn children(&self) -> Vec<&Expr> {
+        vec![&self.lhs, &self.rhs]
     }
 
     pub(crate) fn format<W: std::fmt::Write>(
@@ -248,12 +457,26 @@ impl InfixOp {
             write!(writer, "(")?;
         }
 
+        // Ranges are written without spaces, as in `0..10`
+        let spaced = state.pretty() && !matches!(self.op, Op::Range(_));
+
+        // A negated base needs its parentheses, as `-2 ** 2` negates the power
+        let grouped = matches!(self.op, Op::Arithmetic(ArithmeticOp::Pow))
+            && (matches!(self.lhs.kind(), ExprKind::PrefixOp(_))
+                || number::is_negative(self.lhs.kind()));
+
+        if grouped {
+            write!(writer, "(")?;
+        }
         self.lhs.format(writer, state)?;
-        if state.pretty() {
+        if grouped {
+            write!(writer, ")")?;
+        }
+        if spaced {
             write!(writer, " ")?;
         }
         self.op.format(writer)?;
-        if state.pretty() {
+        if spaced {
             write!(writer, " ")?;
         }
         self.rhs.format(writer, state)?;
@@ -266,6 +489,27 @@ impl InfixOp {
 }
 
 impl Op {
+    /// Returns how tightly the operator binds its operands, higher binding tighter.
+    fn precedence(&self) -> u8 {
+        match self {
+            Op::Range(_) => 0,
+            Op::Coalesce => 1,
+            Op::Logic(LogicOp::Or) => 2,
+            Op::Logic(LogicOp::And) => 3,
+            Op::Comparison(ComparisonOp::Eq | ComparisonOp::NotEq) => 4,
+            Op::Comparison(_) => 5,
+            Op::Bitwise(BitwiseOp::Or) => 6,
+            Op::Bitwise(BitwiseOp::Xor) => 7,
+            Op::Bitwise(BitwiseOp::And) => 8,
+            Op::Bitwise(BitwiseOp::Shl | BitwiseOp::Shr) => 9,
+            Op::Arithmetic(ArithmeticOp::Add | ArithmeticOp::Sub) => 10,
+            Op::Arithmetic(
+                ArithmeticOp::Mul | ArithmeticOp::Div | ArithmeticOp::IntDiv | ArithmeticOp::Rem,
+            ) => 11,
+            Op::Arithmetic(ArithmeticOp::Pow) => 12,
+        }
+    }
+
     pub(crate) fn format<W: std::fmt::Write>(&self, f: &mut W) -> std::fmt::Result {
         let s = match self {
             Op::Arithmetic(math_operator) => match math_operator {
@@ -273,11 +517,26 @@ impl Op {
                 ArithmeticOp::Sub => "-",
                 ArithmeticOp::Mul => "*",
                 ArithmeticOp::Div => "/",
+                ArithmeticOp::IntDiv => "~/",
+                ArithmeticOp::Rem => "%",
+                ArithmeticOp::Pow => "**",
+            },
+            Op::Bitwise(bitwise_operator) => match bitwise_operator {
+                BitwiseOp::And => "&",
+                BitwiseOp::Or => "|",
+                BitwiseOp::Xor => "^",
+                BitwiseOp::Shl => "<<",
+                BitwiseOp::Shr => ">>",
             },
             Op::Logic(logical_operator) => match logical_operator {
                 LogicOp::And => "&&",
                 LogicOp::Or => "||",
             },
+            Op::Coalesce => "??",
+            Op::Range(range_operator) => match range_operator {
+                RangeOp::Exclusive => "..",
+                RangeOp::Inclusive => "..=",
+            },
             Op::Comparison(comparison_operator) => match comparison_operator {
                 ComparisonOp::Eq => "==",
                 ComparisonOp::NotEq => "!=",
@@ -291,32 +550,117 @@ impl Op {
     }
 }
 
-fn handle_math_ops<Num>(op: ArithmeticOp, lhs: Num, rhs: Num) -> Num
-where
-    Num: std::ops::Add<Output = Num>
-        + std::ops::Sub<Output = Num>
-        + std::ops::Mul<Output = Num>
-        + std::ops::Div<Output = Num>
-        + Default
-        + PartialEq,
-{
-    match op {
+fn handle_integer_ops(op: ArithmeticOp, lhs: i64, rhs: i64) -> Result<Value, RuntimeErrorKind> {
+    let result = match op {
+        ArithmeticOp::Add => lhs.checked_add(rhs).ok_or(RuntimeErrorKind::Overflow)?,
+        ArithmeticOp::Sub => lhs.checked_sub(rhs).ok_or(RuntimeErrorKind::Overflow)?,
+        ArithmeticOp::Mul => lhs.checked_mul(rhs).ok_or(RuntimeErrorKind::Overflow)?,
+        // Integer division truncates towards zero
+        ArithmeticOp::Div => lhs.checked_div(rhs).ok_or(division_failed(rhs))?,
+        // Integer division with `~/` rounds towards negative infinity
+        ArithmeticOp::IntDiv => {
+            let quotient = lhs.checked_div(rhs).ok_or(division_failed(rhs))?;
+            if lhs % rhs != 0 && (lhs < 0) != (rhs < 0) {
+                quotient - 1
+            } else {
+                quotient
+            }
+        }
+        // The remainder takes the sign of the dividend
+        ArithmeticOp::Rem => lhs.checked_rem(rhs).ok_or(division_failed(rhs))?,
+        // Negative exponents produce fractions, which divide by zero for a zero base
+        ArithmeticOp::Pow if rhs < 0 && lhs == 0 => return Err(RuntimeErrorKind::DivisionByZero),
+        ArithmeticOp::Pow if rhs < 0 => return Ok(Value::Float((lhs as f64).powf(rhs as f64))),
+        ArithmeticOp::Pow => u32::try_from(rhs)
+            .ok()
+            .and_then(|rhs| lhs.checked_pow(rhs))
+            .ok_or(RuntimeErrorKind::Overflow)?,
+    };
+
+    Ok(Value::Integer(result))
+}
+
+fn handle_float_ops(op: ArithmeticOp, lhs: f64, rhs: f64) -> Result<Value, RuntimeErrorKind> {
+    let result = match op {
         ArithmeticOp::Add => lhs + rhs,
         ArithmeticOp::Sub => lhs - rhs,
         ArithmeticOp::Mul => lhs * rhs,
-        ArithmeticOp::Div => {
-            if rhs == Num::default() {
-                Num::default()
-            } else {
-                lhs / rhs
+        ArithmeticOp::Div | ArithmeticOp::IntDiv | ArithmeticOp::Rem if rhs == 0.0 => {
+            return Err(RuntimeErrorKind::DivisionByZero);
+        }
+        ArithmeticOp::Div => lhs / rhs,
+        ArithmeticOp::IntDiv => {
+            let quotient = (lhs / rhs).floor();
+            // `i64::MAX as f64` rounds up to 2^63, which is out of range
+            if quotient.is_nan() || quotient < i64::MIN as f64 || quotient >= i64::MAX as f64 {
+                return Err(RuntimeErrorKind::Overflow);
             }
+            return Ok(Value::Integer(quotient as i64));
+        }
+        ArithmeticOp::Rem => lhs % rhs,
+        ArithmeticOp::Pow if lhs == 0.0 && rhs < 0.0 => {
+            return Err(RuntimeErrorKind::DivisionByZero);
         }
+        ArithmeticOp::Pow => lhs.powf(rhs),
+    };
+
+    Ok(Value::Float(result))
+}
+
+/// Reports a failed integer division, which either divides by zero or `i64::MIN` by -1.
+fn division_failed(divisor: i64) -> RuntimeErrorKind {
+    if divisor == 0 {
+        RuntimeErrorKind::DivisionByZero
+    } else {
+        RuntimeErrorKind::Overflow
     }
 }
 
-fn handle_logical_ops(op: LogicOp, lhs: bool, rhs: bool) -> bool {
+fn handle_bitwise_ops(op: BitwiseOp, lhs: i64, rhs: i64) -> Result<i64, RuntimeErrorKind> {
     match op {
-        LogicOp::And => lhs && rhs,
-        LogicOp::Or => lhs || rhs,
+        BitwiseOp::And => Ok(lhs & rhs),
+        BitwiseOp::Or => Ok(lhs | rhs),
+        BitwiseOp::Xor => Ok(lhs ^ rhs),
+        // Shifting by a negative amount or by the full width is an overflow
+        BitwiseOp::Shl => u32::try_from(rhs)
+            .ok()
+            .and_then(|rhs| lhs.checked_shl(rhs))
+            .ok_or(RuntimeErrorKind::Overflow),
+        BitwiseOp::Shr => u32::try_from(rhs)
+            .ok()
+            .and_then(|rhs| lhs.checked_shr(rhs))
+            .ok_or(RuntimeErrorKind::Overflow),
+    }
+}
+
+fn handle_comparison_ops(op: ComparisonOp, ordering: Ordering) -> bool {
+    match op {
+        ComparisonOp::Eq => ordering.is_eq(),
+        ComparisonOp::NotEq => ordering.is_ne(),
+        ComparisonOp::Gt => ordering.is_gt(),
+        ComparisonOp::Lt => ordering.is_lt(),
+        ComparisonOp::GtOrEq => ordering.is_ge(),
+        ComparisonOp::LtOrEq => ordering.is_le(),
+    }
+}
+
+fn number_expected(found: &Value) -> RuntimeErrorKind {
+    RuntimeErrorKind::TypeMismatch {
+        expected: "integer or float",
+        found: found.type_name(),
+    }
+}
+
+fn integer_expected(found: &Value) -> RuntimeErrorKind {
+    RuntimeErrorKind::TypeMismatch {
+        expected: "integer",
+        found: found.type_name(),
+    }
+}
+
+fn boolean_expected(found: &Value) -> RuntimeErrorKind {
+    RuntimeErrorKind::TypeMismatch {
+        expected: "boolean",
+        found: found.type_name(),
     }
 }
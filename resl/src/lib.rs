@@ -22,7 +22,7 @@
 //!         port = 8080;
 //!         host = "localhost";
 //!         debug = true;
-//!         url = concat("http://", host, ":", port);
+//!         url = "http://${host}:${port}";
 //!         env = ? debug : "development" | "production";
 //!         ["url": url, "environment": env]
 //!     }
@@ -37,9 +37,12 @@
 //! - **Binary Operations**: Perform arithmetic, logical, and comparison operations
 //! - **Conditional Logic**: Use ternary operators `? condition : then | else`
 //! - **Rich Data Types**: Support for strings, numbers, booleans, lists, and maps
+//! - **String Interpolation**: Embed expressions in strings with `"http://${host}:${port}"`
 //! - **Block Expressions**: Group statements and computations in `{}` blocks
 //! - **Array/Object Access**: Index into collections with `[key]` syntax and range slicing
 //! - **Flexible Structure**: Top-level can be any expression, not just objects
+//! - **Modules**: Split configurations across files with `import("path.resl")`
+//! - **Comments**: Annotate configurations with `//` line and `/* */` block comments
 //!
 //! ## Installation
 //!
@@ -63,6 +66,10 @@
 
 mod block;
 mod boolean;
+mod comment;
+mod de;
+mod document;
+mod engine;
 mod expr;
 mod fn_call;
 mod for_each;
@@ -73,21 +80,34 @@ mod index;
 mod infix;
 mod list;
 mod map;
+mod match_expr;
+mod module;
 mod null;
 mod number;
 mod prefix;
+mod ser;
 mod string;
 
 mod binding;
 mod context;
+mod env;
 mod error;
 mod macros;
+mod span;
 mod state;
 mod utils;
 mod value;
 
-pub use error::ParseError;
-pub use expr::Expr;
+pub use de::{from_str, from_value};
+pub use document::{Document, Name, NameKind};
+pub use engine::Engine;
+pub use error::{
+    DeserializeError, Error, ParseError, RuntimeError, RuntimeErrorKind, SerializeError,
+};
+pub use expr::{Expr, ExprKind};
+pub use module::{DenyResolver, FileResolver, MemoryResolver, Resolver};
+pub use ser::{to_string, to_string_pretty, to_value};
+pub use span::Span;
 pub use value::Value;
 
 pub use crate::state::{CtxState, EvalState, FmtState, ParseState};
@@ -100,6 +120,33 @@ pub use crate::state::{CtxState, EvalState, FmtState, ParseState};
 type StatefulInput<'input, 'state> =
     winnow::Stateful<winnow::LocatingSlice<&'input str>, state::ParseState<'state>>;
 
+/// Parses a RESL expression from a string without evaluating it.
+///
+/// The returned [`Document`] exposes the span of every expression and of every
+/// declared name, which is what editors, linters and other tools need to point
+/// at the source text.
+///
+/// # Arguments
+///
+/// * `input` - The RESL expression string to parse
+///
+/// # Returns
+///
+/// * `Ok(Document)` - The parsed document
+/// * `Err(ParseError)` - If parsing fails
+///
+/// # Examples
+///
+/// ```
+/// use resl::parse;
+///
+/// let document = parse("{x = 5; x * 2}").unwrap();
+/// assert_eq!(document.expr().span().end(), 14);
+/// ```
+pub fn parse(input: &str) -> Result<Document, ParseError> {
+    Document::parse(input)
+}
+
 /// Parses a RESL expression from a string and formats it to a writer.
 ///
 /// This utility function combines parsing and formatting in one operation. It's useful
@@ -135,7 +182,12 @@ pub fn format<W: std::fmt::Write>(
     let expression = Expr::parse_all(input, &mut ctx_state)?;
 
     // For now, ignore IO errors since they're less common than parse errors
-    let _ = expression.format(writer, FmtState::new(pretty, &ctx_state));
+    let pending = comment::Pending::new(ctx_state.comments().keys().copied().collect());
+    let _ = expression.format_all(
+        input.len(),
+        writer,
+        FmtState::new(pretty, &ctx_state, &pending),
+    );
 
     Ok(())
 }
@@ -153,7 +205,9 @@ pub fn format<W: std::fmt::Write>(
 /// # Returns
 ///
 /// * `Ok(Value)` - The evaluated result as a RESL value
-/// * `Err(ParseError)` - If parsing fails
+/// * `Err(Error::Parse)` - If parsing fails
+///
+/// Runtime errors evaluate to `null` instead of failing, see [`evaluate_strict`].
 ///
 /// # Examples
 ///
@@ -181,14 +235,49 @@ pub fn format<W: std::fmt::Write>(
 /// assert_eq!(result.to_string(), "[1, 2, 3]");
 /// // result is a List containing integers 1, 2, 3
 /// ```
-pub fn evaluate(input: &str) -> Result<Value, ParseError> {
-    let mut ctx_state = CtxState::new();
-
-    let expression = Expr::parse_all(input, &mut ctx_state)?;
-
-    let value = expression.evaluate(&mut EvalState::new(&mut ctx_state));
+pub fn evaluate(input: &str) -> Result<Value, Error> {
+    Engine::new().evaluate(input)
+}
 
-    Ok(value)
+/// Evaluates a RESL expression from a string, failing on runtime errors.
+///
+/// Unlike [`evaluate`], which silently produces `null` for failing expressions, strict
+/// evaluation stops at the first type mismatch, arity mismatch, unknown identifier,
+/// cyclic reference or out-of-bounds index and reports it along with its location.
+///
+/// # Arguments
+///
+/// * `input` - A string slice containing the RESL expression to evaluate
+///
+/// # Returns
+///
+/// * `Ok(Value)` - The evaluated result as a RESL value
+/// * `Err(Error::Parse)` - If parsing fails
+/// * `Err(Error::Runtime)` - If evaluation fails
+///
+/// # Examples
+///
+/// ```
+/// use resl::{Error, RuntimeErrorKind, evaluate, evaluate_strict};
+///
+/// let result = evaluate_strict("{x = 10; x * 2}").unwrap();
+/// assert_eq!(result.to_string(), "20");
+///
+/// // Lenient evaluation produces null for unknown identifiers
+/// assert_eq!(evaluate("missing + 1").unwrap().to_string(), "null");
+///
+/// // Strict evaluation reports them instead
+/// let Err(Error::Runtime(err)) = evaluate_strict("missing + 1") else {
+///     panic!("expected a runtime error");
+/// };
+/// assert_eq!(
+///     err.kind,
+///     RuntimeErrorKind::UnknownIdentifier("missing".to_string())
+/// );
+/// assert_eq!((err.line_number, err.column), (1, 1));
+/// ```
+pub fn evaluate_strict(input: &str) -> Result<Value, Error> {
+    Engine::new().strict(true).evaluate(input)
 }
 
 /// Evaluates a RESL expression from a string and writes the formatted result to a writer.
@@ -206,7 +295,7 @@ pub fn evaluate(input: &str) -> Result<Value, ParseError> {
 /// # Returns
 ///
 /// * `Ok(())` - If evaluation and formatting succeed
-/// * `Err(ParseError)` - If parsing or evaluation fails, or write operation fails
+/// * `Err(Error::Parse)` - If parsing fails
 ///
 /// # Examples
 ///
@@ -221,7 +310,7 @@ pub fn evaluate_and_format<W: std::fmt::Write>(
     input: &str,
     writer: &mut W,
     pretty: bool,
-) -> Result<(), ParseError> {
+) -> Result<(), Error> {
     let value = evaluate(input)?;
 
     // For now, ignore IO errors since they're less common than parse errors
@@ -5,10 +5,13 @@ use winnow::{
 
 use crate::{
     StatefulInput,
-    expr::Expr,
+    comment::Attachments,
+    error::EvalResult,
+    expr::{Expr, ExprKind},
     macros::{exp_char, exp_desc, label},
+    span::Span,
     state::{EvalState, FmtState},
-    utils::{delimited_multispace0, write_indent},
+    utils::{delimited_multispace0, spanned, with_bitwise_or, write_indent},
     value::Value,
 };
 
@@ -16,65 +19,87 @@ use crate::{
 pub(crate) type List = Vec<Expr>;
 
 pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-    delimited(
-        '[',
-        delimited_multispace0(separated(0.., Expr::parse, delimited_multispace0(','))),
-        alt((
-            // Trailing comma before closing ']'
-            preceded(
-                delimited_multispace0(','),
-                cut_err(']')
-                    .context(exp_desc!("an expression"))
-                    .context(exp_char!(']')),
+    spanned(
+        delimited(
+            '[',
+            with_bitwise_or(
+                true,
+                delimited_multispace0(separated(0.., Expr::parse, delimited_multispace0(','))),
             ),
-            // No trailing comma
-            cut_err(']').context(exp_char!(',')).context(exp_char!(']')),
-        )),
+            alt((
+                // Trailing comma before closing ']'
+                preceded(
+                    delimited_multispace0(','),
+                    cut_err(']')
+                        .context(exp_desc!("an expression"))
+                        .context(exp_char!(']')),
+                ),
+                // No trailing comma
+                cut_err(']').context(exp_char!(',')).context(exp_char!(']')),
+            )),
+        )
+        .context(label!("list"))
+        .map(ExprKind::List),
     )
-    .context(label!("list"))
-    .map(Expr::List)
     .parse_next(input)
 }
 
-pub(crate) fn evaluate(list: List, state: &mut EvalState) -> Value {
-    Value::List(list.into_iter().map(|expr| expr.evaluate(state)).collect())
+pub(crate) fn evaluate(list: List, state: &mut EvalState) -> EvalResult {
+    list.into_iter()
+        .map(|expr| expr.evaluate(state))
+        .collect::<EvalResult<_>>()
+        .map(Value::List)
+}
+
+/// Returns the items of a list.
+pub(crate) fn children(list: &List) -> Vec<&Expr> {
+    list.iter().collect()
 }
 
 pub(crate) fn format<W: std::fmt::Write>(
     list: &List,
+    span: Span,
     writer: &mut W,
     state: FmtState,
 ) -> std::fmt::Result {
     let pretty = state.pretty();
+    let spans = list.iter().map(Expr::span).collect::<Vec<_>>();
+    let comments = Attachments::new(state, span, &spans);
 
     write!(writer, "[")?;
 
-    if list.is_empty() {
+    if list.is_empty() && !comments.has_dangling() {
         write!(writer, "]")?;
         return Ok(());
     }
 
-    if pretty {
+    if pretty && !list.is_empty() {
         writeln!(writer)?;
         write_indent(writer, state.indented().indent_level())?;
     }
 
-    let mut list_iter = list.iter().peekable();
+    let mut list_iter = list.iter().enumerate().peekable();
 
-    while let Some(expr) = list_iter.next() {
+    while let Some((idx, expr)) = list_iter.next() {
+        comments[idx].format_leading(writer, state.indented())?;
         expr.format(writer, state.indented())?;
 
         if list_iter.peek().is_some() {
             write!(writer, ",")?;
+            comments[idx].format_trailing(writer, state.indented())?;
             if pretty {
                 writeln!(writer)?;
                 write_indent(writer, state.indented().indent_level())?;
             } else {
                 write!(writer, " ")?;
             }
+        } else {
+            comments[idx].format_trailing(writer, state.indented())?;
         }
     }
 
+    comments.format_dangling(writer, state.indented())?;
+
     if pretty {
         writeln!(writer)?;
         write_indent(writer, state.indent_level())?;
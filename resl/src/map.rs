@@ -1,68 +1,145 @@
 use winnow::{
     ModalResult, Parser,
-    combinator::{alt, cut_err, delimited, preceded, separated, separated_pair},
+    combinator::{alt, cut_err, delimited, peek, preceded, separated, separated_pair, terminated},
 };
 
 use crate::{
     StatefulInput,
-    expr::Expr,
+    comment::{self, Attachments},
+    document::NameKind,
+    error::{EvalResult, RuntimeErrorKind},
+    expr::{Expr, ExprKind},
     macros::{exp_char, exp_desc, label},
+    span::{Span, Spanned},
     state::{EvalState, FmtState},
     string,
-    utils::{delimited_multispace0, write_indent},
-    value::Value,
+    utils::{delimited_multispace0, located, spanned, with_bitwise_or, write_indent},
+    value::{Value, ValueMap},
 };
 
-/// Map of key-expression pairs.
-#[cfg(not(feature = "preserve-order"))]
-pub(crate) type Map = std::collections::BTreeMap<String, Expr>;
-#[cfg(feature = "preserve-order")]
-pub(crate) type Map = indexmap::IndexMap<String, Expr>;
+/// Map of key-expression pairs along with the span of each key, in source order.
+pub(crate) type Map = Vec<(Key, Span, Expr)>;
+
+/// Key of a map entry.
+#[derive(Debug, Clone)]
+pub enum Key {
+    /// String literal key.
+    Literal(String),
+    /// Parenthesized expression evaluating to the key.
+    Computed(Expr),
+}
 
 pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-    delimited(
-        '[',
-        delimited_multispace0(separated(
-            1..,
-            separated_pair(
-                delimited('"', string::parse_plain.map(str::to_string), '"'),
-                delimited_multispace0(':'),
-                Expr::require_parse,
-            ),
-            delimited_multispace0(','),
-        ))
-        .map(|list: Vec<(String, Expr)>| list.into_iter().collect::<Map>()),
-        alt((
-            // Trailing comma before closing ']'
-            preceded(
-                delimited_multispace0(','),
-                cut_err(']')
-                    .context(exp_desc!("an expression"))
-                    .context(exp_char!(']')),
+    spanned(
+        delimited(
+            '[',
+            with_bitwise_or(
+                true,
+                delimited_multispace0(separated(
+                    1..,
+                    separated_pair(
+                        located(alt((
+                            parse_literal_key.map(|key| Key::Literal(key.node)),
+                            // Backtrack if not followed by `:`, as `[(a)]` is a list
+                            delimited('(', delimited_multispace0(Expr::require_parse), ')')
+                                .map(Key::Computed),
+                        ))),
+                        delimited_multispace0(':'),
+                        Expr::require_parse,
+                    )
+                    .map(|(key, expr)| (key.node, key.span, expr)),
+                    delimited_multispace0(','),
+                )),
             ),
-            // No trailing comma
-            cut_err(']').context(exp_char!(',')).context(exp_char!(']')),
-        )),
+            alt((
+                // Trailing comma before closing ']'
+                preceded(
+                    delimited_multispace0(','),
+                    cut_err(']')
+                        .context(exp_desc!("an expression"))
+                        .context(exp_char!(']')),
+                ),
+                // No trailing comma
+                cut_err(']').context(exp_char!(',')).context(exp_char!(']')),
+            )),
+        )
+        .context(label!("map"))
+        .map(ExprKind::Map),
     )
-    .context(label!("map"))
-    .map(Expr::Map)
     .parse_next(input)
 }
 
-pub(crate) fn evaluate(map: Map, state: &mut EvalState) -> Value {
-    Value::Map(
-        map.into_iter()
-            .map(|(key, expr)| (key, expr.evaluate(state)))
-            .collect(),
+/// Parses a string literal followed by `:`, recording it as a key.
+pub(crate) fn parse_literal_key(input: &mut StatefulInput) -> ModalResult<Spanned<String>> {
+    let key = terminated(
+        located(string::parse_literal),
+        peek(delimited_multispace0(':')),
     )
+    .parse_next(input)?;
+
+    input
+        .state
+        .record_name(key.node.clone(), NameKind::Key, key.span);
+
+    Ok(key)
+}
+
+pub(crate) fn evaluate(map: Map, state: &mut EvalState) -> EvalResult {
+    let mut value_map = ValueMap::new();
+
+    for (key, _, expr) in map {
+        let key = match key {
+            Key::Literal(key) => key,
+            Key::Computed(key_expr) => {
+                let key_span = key_expr.span();
+                match key_expr.evaluate(state)? {
+                    Value::String(key) => key,
+                    // Entries without a valid key are left out in lenient mode
+                    value => {
+                        state.raise(
+                            RuntimeErrorKind::TypeMismatch {
+                                expected: "string",
+                                found: value.type_name(),
+                            },
+                            key_span,
+                        )?;
+                        continue;
+                    }
+                }
+            }
+        };
+
+        // Later entries replace earlier ones with the same key
+        value_map.insert(key, expr.evaluate(state)?);
+    }
+
+    Ok(Value::Map(value_map))
+}
+
+/// Returns the computed keys and the values of a map, in source order.
+pub(crate) fn children(map: &Map) -> Vec<&Expr> {
+    let mut children = Vec::new();
+    for (key, _, expr) in map {
+        if let Key::Computed(key_expr) = key {
+            children.push(key_expr);
+        }
+        children.push(expr);
+    }
+    children
 }
 
 pub(crate) fn format<W: std::fmt::Write>(
     map: &Map,
+    span: Span,
     writer: &mut W,
     state: FmtState,
 ) -> std::fmt::Result {
     let pretty = state.pretty();
+    let spans = map
+        .iter()
+        .map(|(_, key_span, expr)| key_span.to(expr.span()))
+        .collect::<Vec<_>>();
+    let comments = Attachments::new(state, span, &spans);
 
     write!(writer, "[")?;
 
@@ -76,21 +153,35 @@ pub(crate) fn format<W: std::fmt::Write>(
         write_indent(writer, state.indented().indent_level())?;
     };
 
-    let mut map_iter = map.iter().peekable();
-    while let Some((key, expr)) = map_iter.next() {
-        write!(writer, "\"{key}\": ")?;
+    let mut map_iter = map.iter().enumerate().peekable();
+    while let Some((idx, (key, key_span, expr))) = map_iter.next() {
+        comments[idx].format_leading(writer, state.indented())?;
+        comment::format_node(writer, state, *key_span, |writer| match key {
+            Key::Literal(key) => string::format_literal(writer, state, *key_span, key),
+            Key::Computed(key_expr) => {
+                write!(writer, "(")?;
+                key_expr.format(writer, state.indented())?;
+                write!(writer, ")")
+            }
+        })?;
+        write!(writer, ": ")?;
         expr.format(writer, state.indented())?;
         if map_iter.peek().is_some() {
             write!(writer, ",")?;
+            comments[idx].format_trailing(writer, state.indented())?;
             if pretty {
                 writeln!(writer)?;
                 write_indent(writer, state.indented().indent_level())?;
             } else {
                 write!(writer, " ")?;
             }
+        } else {
+            comments[idx].format_trailing(writer, state.indented())?;
         }
     }
 
+    comments.format_dangling(writer, state.indented())?;
+
     if pretty {
         writeln!(writer)?;
         write_indent(writer, state.indent_level())?;
@@ -0,0 +1,569 @@
+use winnow::{
+    ModalResult, Parser,
+    ascii::multispace1,
+    combinator::{alt, cut_err, delimited, fail, not, opt, peek, preceded, separated, terminated},
+    stream::Location,
+};
+
+use crate::{
+    StatefulInput,
+    binding::Binding,
+    boolean,
+    comment::{self, Attachments},
+    context::Context,
+    document::NameKind,
+    error::{EvalResult, RuntimeErrorKind},
+    expr::{Expr, ExprKind},
+    ident::Ident,
+    macros::{exp_char, exp_desc, exp_str, label},
+    map, null, number,
+    span::{Span, Spanned},
+    state::{EvalState, FmtState},
+    string,
+    utils::{delimited_multispace0, located, with_bitwise_or, write_indent},
+    value::Value,
+};
+
+/// Match expression evaluating the first arm whose pattern fits a value.
+#[derive(Debug, Clone)]
+pub struct Match {
+    value: Box<Expr>,
+    arms: Vec<Arm>,
+}
+
+/// Pattern along with the expression it selects.
+#[derive(Debug, Clone)]
+struct Arm {
+    pattern: Spanned<Pattern>,
+    /// Context binding the names in the pattern
+    ctx_idx: usize,
+    /// Condition the value must also meet
+    guard: Option<Expr>,
+    body: Expr,
+    span: Span,
+}
+
+/// Shape a value is compared against.
+#[derive(Debug, Clone)]
+enum Pattern {
+    /// `_`, which fits any value
+    Wildcard,
+    /// A name, which fits any value and binds it
+    Bind(Ident),
+    /// A string, number, boolean or null, which fits equal values like `==`
+    Literal(Value),
+    /// `[a, b]`, which fits lists of as many items, or of at least as many with a
+    /// rest pattern such as `[a, ...rest]`
+    List(Vec<Spanned<Pattern>>, Option<Box<Spanned<Pattern>>>),
+    /// `["key": a]`, which fits maps having at least the given keys
+    Map(Vec<(Spanned<String>, Spanned<Pattern>)>),
+}
+
+impl Match {
+    /// Parses `match value { pattern => body, ... }`.
+    ///
+    /// `match` is only a keyword when followed by whitespace, a value and `{`, so it
+    /// can still be used as a name.
+    pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
+        let start = input.current_token_start();
+
+        let value = delimited(
+            terminated("match", peek(multispace1)),
+            with_bitwise_or(true, Expr::parse),
+            '{',
+        )
+        .parse_next(input)?;
+
+        let arms = with_bitwise_or(
+            true,
+            delimited_multispace0(alt((
+                separated(1.., Self::parse_arm, delimited_multispace0(',')),
+                cut_err(fail).context(exp_desc!("a pattern")),
+            ))),
+        )
+        .context(label!("match expression"))
+        .parse_next(input)?;
+
+        alt((
+            // Trailing comma before closing '}'
+            preceded(
+                delimited_multispace0(','),
+                cut_err('}')
+                    .context(exp_desc!("a pattern"))
+                    .context(exp_char!('}')),
+            ),
+            // No trailing comma
+            cut_err('}').context(exp_char!(',')).context(exp_char!('}')),
+        ))
+        .context(label!("match expression"))
+        .parse_next(input)?;
+
+        Ok(Expr::new(
+            ExprKind::Match(Self {
+                value: Box::new(value),
+                arms,
+            }),
+            Span::new(start, input.previous_token_end()),
+        ))
+    }
+
+    /// Parses an arm, whose guard and body are parsed in a context binding the
+    /// names in its pattern.
+    fn parse_arm(input: &mut StatefulInput) -> ModalResult<Arm> {
+        let start = input.current_token_start();
+        let pattern = Pattern::parse(input)?;
+
+        let mut names = Vec::new();
+        pattern.node.names(&mut names);
+
+        // A name can only be bound once
+        if names
+            .iter()
+            .enumerate()
+            .any(|(idx, name)| names[..idx].contains(name))
+        {
+            return cut_err(fail)
+                .context(exp_desc!("distinct names in the pattern"))
+                .parse_next(input);
+        }
+
+        // Store the current active context index before parsing
+        let current_ctx_idx = input.state.active_ctx_idx();
+
+        // Get the next available context index
+        let ctx_idx = input.state.avail_ctx_idx();
+
+        // Increment the available context index for nested expressions
+        input.state.increment_avail_ctx_idx();
+
+        // Set this new context as active
+        // This ensures that contexts of nested exprs have this context as parent
+        input.state.set_active_ctx(ctx_idx);
+
+        let parse_result = (
+            // Optional guard
+            opt(preceded(delimited_multispace0('?'), Expr::require_parse)),
+            preceded(
+                delimited_multispace0(cut_err("=>").context(exp_str!("=>"))),
+                Expr::require_parse,
+            ),
+        )
+            .parse_next(input);
+
+        // Restore active context to previous one
+        input.state.set_active_ctx(current_ctx_idx);
+
+        let (guard, body) = parse_result.inspect_err(|_| {
+            // Decrement avail_ctx_idx to avoid skipping indices
+            input.state.decrement_avail_ctx_idx();
+        })?;
+
+        // The names are bound to the parts of the value when the arm is evaluated
+        let ctx = Context::from_iter(
+            Some(current_ctx_idx),
+            names.into_iter().map(|name| (name, Binding::default())),
+        );
+        input.state.place_ctx(ctx_idx, ctx);
+
+        // The body may be followed by whitespace parsed while looking for an operator
+        let span = Span::new(start, body.span().end());
+
+        Ok(Arm {
+            pattern,
+            ctx_idx,
+            guard,
+            body,
+            span,
+        })
+    }
+
+    pub(crate) fn evaluate(self, state: &mut EvalState) -> EvalResult {
+        let value_span = self.value.span();
+        let value = self.value.evaluate(state)?;
+        let parent = state.env().clone();
+
+        for arm in self.arms {
+            let mut bindings = Vec::new();
+            if !arm.pattern.node.matches(&value, &mut bindings) {
+                continue;
+            }
+
+            // Bind the names in the pattern in a new environment for this arm
+            let env = state.new_env(arm.ctx_idx, parent.clone(), bindings);
+            if let Some(result) = state.with_env(env, |state| arm.evaluate(state))? {
+                return Ok(result);
+            }
+        }
+
+        state.raise(RuntimeErrorKind::NoMatch(value.to_string()), value_span)
+    }
+
+    /// Returns the value along with the guard and body of every arm, in source order.
+    pub(crate) fn children(&self) -> Vec<&Expr> {
+        let mut children = vec![self.value.as_ref()];
+        for arm in &self.arms {
+            children.extend(arm.guard.as_ref());
+            children.push(&arm.body);
+        }
+        children
+    }
+
+    pub(crate) fn format<W: std::fmt::Write>(
+        &self,
+        span: Span,
+        writer: &mut W,
+        state: FmtState,
+    ) -> std::fmt::Result {
+        let pretty = state.pretty();
+
+        write!(writer, "match ")?;
+        self.value.format(writer, state)?;
+
+        if pretty {
+            write!(writer, " ")?;
+        }
+
+        write!(writer, "{{")?;
+
+        // Comments before `{` are left to the value
+        let spans = self.arms.iter().map(|arm| arm.span).collect::<Vec<_>>();
+        let brace = comment::token_after(state, self.value.span().end());
+        let comments = Attachments::new(state, Span::new(brace + 1, span.end()), &spans);
+
+        let mut arms_iter = self.arms.iter().enumerate().peekable();
+
+        // Each arm is written on its own line when pretty-printing
+        while let Some((idx, arm)) = arms_iter.next() {
+            if pretty {
+                writeln!(writer)?;
+                write_indent(writer, state.indented().indent_level())?;
+            }
+
+            comments[idx].format_leading(writer, state.indented())?;
+            arm.format(writer, state.indented())?;
+
+            if arms_iter.peek().is_some() {
+                write!(writer, ",")?;
+            }
+
+            comments[idx].format_trailing(writer, state.indented())?;
+
+            // Keeps the comment apart from the next arm
+            if !pretty && comments[idx].has_trailing() && arms_iter.peek().is_some() {
+                write!(writer, " ")?;
+            }
+        }
+
+        comments.format_dangling(writer, state.indented())?;
+
+        if pretty {
+            writeln!(writer)?;
+            write_indent(writer, state.indent_level())?;
+        }
+
+        write!(writer, "}}")
+    }
+}
+
+impl Arm {
+    /// Evaluates the guard and body in the environment binding the pattern.
+    ///
+    /// Returns `None` if the guard is not met.
+    fn evaluate(self, state: &mut EvalState) -> EvalResult<Option<Value>> {
+        if let Some(guard) = self.guard {
+            let guard_span = guard.span();
+            match guard.evaluate(state)? {
+                Value::Boolean(true) => {}
+                Value::Boolean(false) => return Ok(None),
+                value => {
+                    // Guards that are not booleans are not met in lenient mode
+                    state.raise(
+                        RuntimeErrorKind::TypeMismatch {
+                            expected: "boolean",
+                            found: value.type_name(),
+                        },
+                        guard_span,
+                    )?;
+                    return Ok(None);
+                }
+            }
+        }
+
+        self.body.evaluate(state).map(Some)
+    }
+
+    fn format<W: std::fmt::Write>(&self, writer: &mut W, state: FmtState) -> std::fmt::Result {
+        let pretty = state.pretty();
+
+        self.pattern.node.format(self.pattern.span, writer, state)?;
+
+        if let Some(guard) = &self.guard {
+            if pretty {
+                write!(writer, " ")?;
+            }
+
+            write!(writer, "?")?;
+
+            if pretty {
+                write!(writer, " ")?;
+            }
+
+            guard.format(writer, state)?;
+        }
+
+        if pretty {
+            write!(writer, " ")?;
+        }
+
+        write!(writer, "=>")?;
+
+        if pretty {
+            write!(writer, " ")?;
+        }
+
+        self.body.format(writer, state)
+    }
+}
+
+impl Pattern {
+    fn parse(input: &mut StatefulInput) -> ModalResult<Spanned<Self>> {
+        located(alt((
+            string::parse_literal.map(|s| Self::Literal(Value::String(s))),
+            // Numbers must be parsed before names, which may start with digits
+            number::parse.map(Self::from_literal),
+            Self::parse_name,
+            // Boolean and null must be parsed after names, which reject them
+            boolean::parse.map(Self::from_literal),
+            null::parse.map(Self::from_literal),
+            // Map must be parsed before List
+            Self::parse_map,
+            Self::parse_list,
+        )))
+        .context(label!("pattern"))
+        .parse_next(input)
+    }
+
+    /// Parses `_` or a name binding the value.
+    fn parse_name(input: &mut StatefulInput) -> ModalResult<Self> {
+        alt((
+            terminated('_', not(string::parse_plain)).value(Self::Wildcard),
+            |input: &mut StatefulInput| {
+                let name = located(Ident::parse_ident).parse_next(input)?;
+                input.state.record_ident(&name, NameKind::Parameter);
+                Ok(Self::Bind(name.node))
+            },
+        ))
+        .parse_next(input)
+    }
+
+    fn from_literal(expr: Expr) -> Self {
+        match expr.into_kind() {
+            ExprKind::Int(int) => Self::Literal(Value::Integer(int)),
+            ExprKind::Float(float) => Self::Literal(Value::Float(float)),
+            ExprKind::Bool(bool) => Self::Literal(Value::Boolean(bool)),
+            ExprKind::Null => Self::Literal(Value::Null),
+            _ => unreachable!("Only literals are parsed as patterns"),
+        }
+    }
+
+    /// Parses a list pattern, whose last item may be a rest pattern such as `...rest`.
+    fn parse_list(input: &mut StatefulInput) -> ModalResult<Self> {
+        '['.parse_next(input)?;
+
+        let mut rest = None;
+
+        let items: Vec<_> = separated(
+            0..,
+            |input: &mut StatefulInput| {
+                // The rest pattern must be the last one
+                if rest.is_some() {
+                    return cut_err(fail).context(exp_char!(']')).parse_next(input);
+                }
+                match opt(delimited_multispace0(preceded(
+                    "...",
+                    cut_err(located(Self::parse_name))
+                        .context(exp_desc!("name of the rest pattern")),
+                )))
+                .parse_next(input)?
+                {
+                    Some(pattern) => {
+                        rest = Some(Box::new(pattern));
+                        Ok(None)
+                    }
+                    None => delimited_multispace0(Self::parse)
+                        .map(Some)
+                        .parse_next(input),
+                }
+            },
+            delimited_multispace0(','),
+        )
+        .parse_next(input)?;
+
+        let items = items.into_iter().flatten().collect::<Vec<_>>();
+
+        if items.is_empty() && rest.is_none() {
+            delimited_multispace0(cut_err(']'))
+                .context(exp_desc!("a pattern"))
+                .context(exp_char!(']'))
+                .parse_next(input)?;
+        } else {
+            // Allow a trailing comma
+            opt(delimited_multispace0(',')).parse_next(input)?;
+            cut_err(delimited_multispace0(']'))
+                .context(exp_char!(','))
+                .context(exp_char!(']'))
+                .parse_next(input)?;
+        }
+
+        Ok(Self::List(items, rest))
+    }
+
+    /// Parses a map pattern, backtracking until a key and `:` are found.
+    fn parse_map(input: &mut StatefulInput) -> ModalResult<Self> {
+        delimited(
+            '[',
+            separated(
+                1..,
+                (
+                    delimited_multispace0(map::parse_literal_key),
+                    preceded(
+                        ':',
+                        cut_err(delimited_multispace0(Self::parse)).context(exp_desc!("a pattern")),
+                    ),
+                ),
+                delimited_multispace0(','),
+            ),
+            alt((
+                // Trailing comma before closing ']'
+                preceded(
+                    delimited_multispace0(','),
+                    cut_err(']')
+                        .context(exp_desc!("a key"))
+                        .context(exp_char!(']')),
+                ),
+                // No trailing comma
+                cut_err(']').context(exp_char!(',')).context(exp_char!(']')),
+            )),
+        )
+        .map(Self::Map)
+        .parse_next(input)
+    }
+
+    /// Collects the names bound by the pattern, in order.
+    fn names(&self, names: &mut Vec<Ident>) {
+        match self {
+            Self::Wildcard | Self::Literal(..) => {}
+            Self::Bind(ident) => names.push(ident.clone()),
+            Self::List(items, rest) => {
+                items
+                    .iter()
+                    .chain(rest.as_deref())
+                    .for_each(|item| item.node.names(names));
+            }
+            Self::Map(entries) => entries.iter().for_each(|(_, item)| item.node.names(names)),
+        }
+    }
+
+    /// Returns whether the value fits the pattern, collecting the values of the
+    /// names it binds.
+    fn matches(&self, value: &Value, bindings: &mut Vec<(Ident, Value)>) -> bool {
+        match (self, value) {
+            (Self::Wildcard, _) => true,
+            (Self::Bind(ident), value) => {
+                bindings.push((ident.clone(), value.clone()));
+                true
+            }
+            (Self::Literal(literal), value) => literal.equals(value),
+            (Self::List(items, rest), Value::List(list)) => {
+                let fits = match rest {
+                    Some(_) => list.len() >= items.len(),
+                    None => list.len() == items.len(),
+                };
+
+                fits && items
+                    .iter()
+                    .zip(list)
+                    .all(|(item, value)| item.node.matches(value, bindings))
+                    && rest.as_ref().is_none_or(|rest| {
+                        rest.node
+                            .matches(&Value::List(list[items.len()..].to_vec()), bindings)
+                    })
+            }
+            (Self::Map(entries), Value::Map(map)) => entries.iter().all(|(key, item)| {
+                map.get(&key.node)
+                    .is_some_and(|value| item.node.matches(value, bindings))
+            }),
+            _ => false,
+        }
+    }
+
+    /// Formats the pattern spanning `span`, along with the comments around it.
+    fn format<W: std::fmt::Write>(
+        &self,
+        span: Span,
+        writer: &mut W,
+        state: FmtState,
+    ) -> std::fmt::Result {
+        comment::format_node(writer, state, span, |writer| {
+            self.format_node(span, writer, state)
+        })
+    }
+
+    fn format_node<W: std::fmt::Write>(
+        &self,
+        span: Span,
+        writer: &mut W,
+        state: FmtState,
+    ) -> std::fmt::Result {
+        let separator = if state.pretty() { ", " } else { "," };
+
+        match self {
+            Self::Wildcard => write!(writer, "_"),
+            Self::Bind(ident) => ident.format(writer, state),
+            Self::Literal(value @ (Value::Integer(_) | Value::Float(_))) => {
+                number::write_literal(writer, state, span, value)
+            }
+            Self::Literal(Value::String(s)) => string::format_literal(writer, state, span, s),
+            Self::Literal(value) => value.format(writer, false, 0),
+            Self::List(items, rest) => {
+                write!(writer, "[")?;
+
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        write!(writer, "{separator}")?;
+                    }
+                    item.node.format(item.span, writer, state)?;
+                }
+
+                if let Some(rest) = rest {
+                    if !items.is_empty() {
+                        write!(writer, "{separator}")?;
+                    }
+                    write!(writer, "...")?;
+                    rest.node.format(rest.span, writer, state)?;
+                }
+
+                write!(writer, "]")
+            }
+            Self::Map(entries) => {
+                write!(writer, "[")?;
+
+                for (idx, (key, item)) in entries.iter().enumerate() {
+                    if idx > 0 {
+                        write!(writer, "{separator}")?;
+                    }
+                    comment::format_node(writer, state, key.span, |writer| {
+                        string::format_literal(writer, state, key.span, &key.node)
+                    })?;
+                    write!(writer, ":")?;
+                    if state.pretty() {
+                        write!(writer, " ")?;
+                    }
+                    item.node.format(item.span, writer, state)?;
+                }
+
+                write!(writer, "]")
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    error::{EvalResult, RuntimeErrorKind},
+    expr::Expr,
+    span::Span,
+    state::EvalState,
+    value::Value,
+};
+
+/// Locates and loads the source of modules referenced by `import`.
+///
+/// Resolution happens in two steps: [`Resolver::resolve`] turns the path written in
+/// the document into a module id, and [`Resolver::load`] reads the source for that id.
+/// Module ids identify modules for caching and cycle detection, so two paths that
+/// refer to the same module must resolve to the same id.
+///
+/// # Examples
+///
+/// ```
+/// use resl::{Engine, MemoryResolver, Value};
+///
+/// let resolver = MemoryResolver::new()
+///     .with_module("common.resl", "[\"region\": \"eu-west-1\"]")
+///     .with_module(
+///         "services/api.resl",
+///         "{common = import(\"../common.resl\"); common[\"region\"]}",
+///     );
+///
+/// let engine = Engine::new().resolver(resolver);
+///
+/// let result = engine.evaluate("import(\"services/api.resl\")").unwrap();
+/// assert_eq!(result, Value::String("eu-west-1".to_string()));
+/// ```
+pub trait Resolver: Send + Sync {
+    /// Resolves `path` to a module id.
+    ///
+    /// `importer` is the id of the module containing the `import`, or `None` for the
+    /// document passed to the engine.
+    fn resolve(&self, path: &str, importer: Option<&str>) -> io::Result<String>;
+
+    /// Loads the source of the module with the given id.
+    fn load(&self, id: &str) -> io::Result<String>;
+}
+
+/// Refuses every import.
+///
+/// This is the resolver of a default engine, so documents evaluated with
+/// [`evaluate`](crate::evaluate) or [`from_str`](crate::from_str) cannot read files
+/// from the host. Hosts opt into imports with [`Engine::resolver`](crate::Engine::resolver).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DenyResolver;
+
+impl Resolver for DenyResolver {
+    fn resolve(&self, _path: &str, _importer: Option<&str>) -> io::Result<String> {
+        Err(imports_disabled())
+    }
+
+    fn load(&self, _id: &str) -> io::Result<String> {
+        Err(imports_disabled())
+    }
+}
+
+fn imports_disabled() -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, "imports are disabled")
+}
+
+/// Resolves modules from the file system.
+///
+/// Paths are resolved relative to the directory of the importing file. Imports in
+/// the document passed to the engine are resolved relative to the base directory,
+/// which defaults to the current working directory. Module ids are canonical paths.
+///
+/// Only files inside the base directory can be imported. Paths leading outside of
+/// it, whether absolute, through `..` segments or through symbolic links, are refused.
+#[derive(Debug, Clone)]
+pub struct FileResolver {
+    base_dir: PathBuf,
+}
+
+impl FileResolver {
+    /// Creates a resolver resolving top-level imports relative to `base_dir`.
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+        }
+    }
+}
+
+impl Default for FileResolver {
+    fn default() -> Self {
+        Self::new(".")
+    }
+}
+
+impl Resolver for FileResolver {
+    fn resolve(&self, path: &str, importer: Option<&str>) -> io::Result<String> {
+        let base_dir = self.base_dir.canonicalize()?;
+
+        let dir = match importer {
+            Some(importer) => Path::new(importer).parent().unwrap_or(&base_dir),
+            None => &base_dir,
+        };
+
+        let path = dir.join(path).canonicalize()?;
+
+        // Checked on the canonical path, as `..` and symbolic links may lead anywhere
+        if !path.starts_with(&base_dir) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "path is outside the base directory",
+            ));
+        }
+
+        path.into_os_string()
+            .into_string()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "path is not valid UTF-8"))
+    }
+
+    fn load(&self, id: &str) -> io::Result<String> {
+        std::fs::read_to_string(id)
+    }
+}
+
+/// Resolves modules from sources held in memory.
+///
+/// Modules are registered under `/`-separated paths. Paths are resolved relative to
+/// the importing module, and `.` and `..` segments are normalized, so
+/// `"../common.resl"` imported from `"services/api.resl"` refers to `"common.resl"`.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryResolver {
+    modules: HashMap<String, String>,
+}
+
+impl MemoryResolver {
+    /// Creates a resolver without any modules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a module with the given path and source.
+    pub fn with_module(mut self, path: impl AsRef<str>, source: impl Into<String>) -> Self {
+        self.insert(path, source);
+        self
+    }
+
+    /// Adds a module with the given path and source, replacing any previous one.
+    pub fn insert(&mut self, path: impl AsRef<str>, source: impl Into<String>) {
+        self.modules.insert(normalize(path.as_ref()), source.into());
+    }
+}
+
+impl Resolver for MemoryResolver {
+    fn resolve(&self, path: &str, importer: Option<&str>) -> io::Result<String> {
+        let dir = importer
+            .and_then(|importer| importer.rsplit_once('/'))
+            .map(|(dir, _)| dir)
+            .unwrap_or_default();
+
+        let id = normalize(&format!("{dir}/{path}"));
+
+        if self.modules.contains_key(&id) {
+            Ok(id)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("module `{id}` does not exist"),
+            ))
+        }
+    }
+
+    fn load(&self, id: &str) -> io::Result<String> {
+        self.modules.get(id).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("module `{id}` does not exist"),
+            )
+        })
+    }
+}
+
+/// Normalizes a `/`-separated path, dropping empty and `.` segments and resolving `..`.
+fn normalize(path: &str) -> String {
+    let mut segments = Vec::new();
+
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    segments.join("/")
+}
+
+/// Modules evaluated during a single evaluation.
+#[derive(Debug, Default)]
+pub(crate) struct Modules {
+    /// Values of modules that finished evaluating, by module id
+    cache: HashMap<String, Value>,
+    /// Ids of modules currently being evaluated, innermost last
+    stack: Vec<String>,
+}
+
+/// Evaluates the module at `path`, imported by the module currently being evaluated.
+pub(crate) fn import(state: &mut EvalState, path: &str, span: Span) -> EvalResult {
+    let engine = state.engine();
+    let resolver = engine.module_resolver();
+
+    let importer = state.modules().stack.last().map(String::as_str);
+    let id = match resolver.resolve(path, importer) {
+        Ok(id) => id,
+        Err(err) => return import_failed(state, path, err.to_string(), span),
+    };
+
+    if state.modules().stack.contains(&id) {
+        return state.raise(RuntimeErrorKind::CyclicImport(path.to_string()), span);
+    }
+
+    if let Some(value) = state.modules().cache.get(&id) {
+        return Ok(value.clone());
+    }
+
+    let source = match resolver.load(&id) {
+        Ok(source) => source,
+        Err(err) => return import_failed(state, path, err.to_string(), span),
+    };
+
+    // Each module is parsed into its own contexts, sharing only the engine's bindings
+    let mut ctx_state = engine.ctx_state();
+    let expression = match Expr::parse_all(&source, &mut ctx_state) {
+        Ok(expression) => expression,
+        Err(err) => {
+            let reason = format!(
+                "invalid syntax at line {}, column {}",
+                err.line_number, err.column
+            );
+            return import_failed(state, path, reason, span);
+        }
+    };
+
+    state.modules().stack.push(id);
+    let (modules, closure_cache) = state.shared();
+    let result = expression.evaluate(&mut EvalState::new(
+        Arc::new(ctx_state),
+        engine,
+        modules,
+        closure_cache,
+    ));
+    let id = state.modules().stack.pop().expect("Module is on the stack");
+
+    match result {
+        Ok(value) => {
+            state.modules().cache.insert(id, value.clone());
+            Ok(value)
+        }
+        Err(err) => {
+            let err = err.locate(&source);
+            let kind = match &err.kind {
+                // Import failures only describe paths and kinds of failures as well
+                kind @ (RuntimeErrorKind::ImportFailed { .. }
+                | RuntimeErrorKind::CyclicImport(_)) => kind.to_string(),
+                kind => kind.title().to_string(),
+            };
+            let reason = format!("{kind} at line {}, column {}", err.line_number, err.column);
+            import_failed(state, path, reason, span)
+        }
+    }
+}
+
+/// Reports a module that could not be resolved, loaded, parsed or evaluated.
+///
+/// The reason never includes the source of the module, so that importing a file
+/// that is not a module does not reveal its content.
+fn import_failed(state: &EvalState, path: &str, reason: String, span: Span) -> EvalResult {
+    state.raise(
+        RuntimeErrorKind::ImportFailed {
+            path: path.to_string(),
+            reason,
+        },
+        span,
+    )
+}
@@ -4,16 +4,21 @@ use winnow::{
     combinator::{alt, cut_err, fail, peek, preceded},
 };
 
-use crate::{StatefulInput, expr::Expr, macros::exp_desc};
+use crate::{
+    StatefulInput,
+    expr::{Expr, ExprKind},
+    macros::exp_desc,
+    utils::spanned,
+};
 
 pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-    alt((
-        "null".value(Expr::Null),
+    spanned(alt((
+        "null".value(ExprKind::Null),
         // Fail if "null" is not correctly cased
         preceded(
             peek(Caseless("null")),
             cut_err(fail).context(exp_desc!("null")),
         ),
-    ))
+    )))
     .parse_next(input)
 }
@@ -1,38 +1,171 @@
 use winnow::{
     ModalResult, Parser,
-    ascii::digit1,
-    combinator::{cut_err, opt},
+    combinator::{alt, cut_err, fail, not, opt, peek, preceded, repeat, terminated},
+    error::{ContextError, ErrMode},
+    stream::Stream,
+    token::{one_of, take_while},
 };
 
 use crate::{
-    StatefulInput,
-    expr::Expr,
+    StatefulInput, comment,
+    expr::{Expr, ExprKind},
     macros::{exp_desc, label},
+    span::Span,
+    state::FmtState,
+    utils::spanned,
+    value::Value,
 };
 
 pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-    (
+    let number =
+        spanned(alt((parse_radix, parse_decimal)).context(label!("number"))).parse_next(input)?;
+
+    // `**` binds tighter than a minus sign, so `-2 ** 2` is left to the prefix operator
+    if is_negative(number.kind())
+        && opt(peek(preceded(comment::skip, "**")))
+            .parse_next(input)?
+            .is_some()
+    {
+        return fail.parse_next(input);
+    }
+
+    Ok(number)
+}
+
+/// Returns whether `kind` is a number literal written with a minus sign.
+pub(crate) fn is_negative(kind: &ExprKind) -> bool {
+    match kind {
+        ExprKind::Int(int) => *int < 0,
+        ExprKind::Float(float) => float.is_sign_negative(),
+        _ => false,
+    }
+}
+
+/// Parses a hexadecimal, octal or binary integer such as `0xff`, `0o644` or `0b1010`.
+fn parse_radix(input: &mut StatefulInput) -> ModalResult<ExprKind> {
+    let start = input.checkpoint();
+
+    let (minus, (radix, digits)) = (
+        opt('-'),
+        alt((
+            preceded(
+                "0x",
+                cut_err(separated_digits(|c| c.is_ascii_hexdigit()))
+                    .context(exp_desc!("hexadecimal digit")),
+            )
+            .map(|digits| (16, digits)),
+            preceded(
+                "0o",
+                cut_err(separated_digits(|c| c.is_digit(8))).context(exp_desc!("octal digit")),
+            )
+            .map(|digits| (8, digits)),
+            preceded(
+                "0b",
+                cut_err(separated_digits(|c| c.is_digit(2))).context(exp_desc!("binary digit")),
+            )
+            .map(|digits| (2, digits)),
+        )),
+    )
+        .parse_next(input)?;
+
+    let digits = format!(
+        "{}{}",
+        if minus.is_some() { "-" } else { "" },
+        digits.replace('_', "")
+    );
+
+    match i64::from_str_radix(&digits, radix) {
+        Ok(int) => Ok(ExprKind::Int(int)),
+        Err(_) => out_of_range(input, &start),
+    }
+}
+
+/// Parses a decimal integer or float such as `-42`, `1_000_000`, `3.14` or `1e-3`.
+fn parse_decimal(input: &mut StatefulInput) -> ModalResult<ExprKind> {
+    let start = input.checkpoint();
+
+    let string = (
         // An optional leading minus sign
         opt('-'),
-        (
-            // Integral part
-            digit1,
-            // Fractional part
-            opt((
-                '.',
-                // Require at least one digit after the decimal point
-                cut_err(digit1).context(exp_desc!("fractional part")),
-            )),
-        ),
+        // Integral part
+        separated_digits(|c| c.is_ascii_digit()),
+        // Fractional part
+        opt((
+            // A second `.` starts a range such as `0..10`
+            terminated('.', not('.')),
+            // Require at least one digit after the decimal point
+            cut_err(separated_digits(|c| c.is_ascii_digit())).context(exp_desc!("fractional part")),
+        )),
+        // Exponent
+        opt((
+            one_of(['e', 'E']),
+            opt(one_of(['+', '-'])),
+            cut_err(separated_digits(|c| c.is_ascii_digit())).context(exp_desc!("exponent")),
+        )),
+    )
+        .take()
+        .parse_next(input)?;
+
+    let string = string.replace('_', "");
+
+    if string.contains(['.', 'e', 'E']) {
+        Ok(ExprKind::Float(
+            string.parse::<f64>().expect("Float literal is valid"),
+        ))
+    } else {
+        match string.parse::<i64>() {
+            Ok(int) => Ok(ExprKind::Int(int)),
+            Err(_) => out_of_range(input, &start),
+        }
+    }
+}
+
+/// Parses digits that may be separated by single underscores, as in `1_000`.
+fn separated_digits<'input, 'state>(
+    is_digit: fn(char) -> bool,
+) -> impl Parser<StatefulInput<'input, 'state>, &'input str, ErrMode<ContextError>> {
+    (
+        take_while(1.., is_digit),
+        repeat::<_, _, (), _, _>(0.., ('_', take_while(1.., is_digit))),
     )
         .take()
-        .context(label!("decimal"))
-        .map(|string: &str| {
-            if string.contains('.') {
-                Expr::Float(string.parse::<f64>().unwrap())
-            } else {
-                Expr::Int(string.parse::<i64>().unwrap())
-            }
-        })
+}
+
+/// Fails at the start of an integer literal that does not fit in 64 bits.
+fn out_of_range<'input, 'state, T>(
+    input: &mut StatefulInput<'input, 'state>,
+    start: &<StatefulInput<'input, 'state> as Stream>::Checkpoint,
+) -> ModalResult<T> {
+    input.reset(start);
+    cut_err(fail)
+        .context(exp_desc!("integer within the 64-bit range"))
         .parse_next(input)
 }
+
+/// Writes a number literal as written in the source, keeping its base, digit
+/// separators and exponent, or `value` if the source is not available.
+pub(crate) fn write_literal<W: std::fmt::Write>(
+    writer: &mut W,
+    state: FmtState,
+    span: Span,
+    value: &Value,
+) -> std::fmt::Result {
+    match (state.source_text(span), value) {
+        (Some(text), _) => writer.write_str(text),
+        (None, Value::Float(float)) => write_float(writer, *float),
+        (None, value) => write!(writer, "{value}"),
+    }
+}
+
+/// Writes a float so that it is read back as a float, including `inf` and `nan`.
+pub(crate) fn write_float<W: std::fmt::Write>(writer: &mut W, float: f64) -> std::fmt::Result {
+    if float.is_nan() {
+        write!(writer, "nan")
+    } else if float.is_infinite() {
+        write!(writer, "{}inf", if float < 0.0 { "-" } else { "" })
+    } else {
+        // Unlike `Display`, `Debug` keeps the fraction of whole numbers and uses an
+        // exponent for very large or small magnitudes
+        write!(writer, "{:?}", float)
+    }
+}
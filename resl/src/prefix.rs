@@ -1,11 +1,16 @@
-use winnow::{ModalResult, Parser, combinator::alt};
+use winnow::{
+    ModalResult, Parser,
+    combinator::{alt, cut_err},
+};
 
 use crate::{
     StatefulInput,
-    expr::Expr,
-    macros::label,
+    error::{EvalResult, RuntimeErrorKind},
+    expr::{Expr, ExprKind},
+    infix::InfixOp,
+    macros::{exp_desc, label},
     state::{EvalState, FmtState},
-    utils::delimited_multispace0,
+    utils::{delimited_multispace0, spanned},
     value::Value,
 };
 
@@ -25,33 +30,52 @@ pub(crate) enum Op {
 
 impl PrefixOp {
     pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-        (
-            delimited_multispace0(alt(('-'.value(Op::Negate), '!'.value(Op::Not)))),
-            Expr::require_parse.map(Box::new),
+        spanned(
+            (
+                delimited_multispace0(alt(('-'.value(Op::Negate), '!'.value(Op::Not)))),
+                // Prefix operators bind tighter than any infix operator but `**`
+                cut_err(InfixOp::parse_power)
+                    .context(exp_desc!("operand"))
+                    .map(Box::new),
+            )
+                .context(label!("prefix operation"))
+                .map(|(op, operand)| PrefixOp { op, operand })
+                .map(ExprKind::PrefixOp),
         )
-            .context(label!("prefix operation"))
-            .map(|(op, operand)| PrefixOp { op, operand })
-            .map(Expr::PrefixOp)
-            .parse_next(input)
+        .parse_next(input)
     }
 
-    pub(crate) fn compute(self, state: &mut EvalState) -> Value {
-        let value = self.operand.evaluate(state);
+    pub(crate) fn compute(self, state: &mut EvalState) -> EvalResult {
+        let operand_span = self.operand.span();
+        let value = self.operand.evaluate(state)?;
 
-        match value {
-            Value::Integer(int) if self.op == Op::Negate => {
-                return Value::Integer(-int);
-            }
-            Value::Float(float) if self.op == Op::Negate => {
-                return Value::Float(-float);
-            }
-            Value::Boolean(bool) if self.op == Op::Not => {
-                return Value::Boolean(!bool);
-            }
-            _ => {}
+        match (self.op, value) {
+            (Op::Negate, Value::Integer(int)) => match int.checked_neg() {
+                Some(int) => Ok(Value::Integer(int)),
+                None => state.raise(RuntimeErrorKind::Overflow, operand_span),
+            },
+            (Op::Negate, Value::Float(float)) => Ok(Value::Float(-float)),
+            (Op::Not, Value::Boolean(bool)) => Ok(Value::Boolean(!bool)),
+            (Op::Negate, value) => state.raise(
+                RuntimeErrorKind::TypeMismatch {
+                    expected: "integer or float",
+                    found: value.type_name(),
+                },
+                operand_span,
+            ),
+            (Op::Not, value) => state.raise(
+                RuntimeErrorKind::TypeMismatch {
+                    expected: "boolean",
+                    found: value.type_name(),
+                },
+                operand_span,
+            ),
         }
+    }
 
-        Value::Null
+    /// Returns the operand.
+    pub(crate) fn children(&self) -> Vec<&Expr> {
+        vec![&self.operand]
     }
 
     pub(crate) fn format<W: std::fmt::Write>(
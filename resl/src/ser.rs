@@ -0,0 +1,625 @@
+use serde::ser::{self, Serialize};
+
+use crate::{
+    error::SerializeError,
+    value::{Value, ValueList, ValueMap},
+};
+
+/// Serializes a value into compact RESL source text.
+///
+/// The output uses the same layout as [`Value::write_formatted`] without
+/// pretty-printing, so it can be read back with [`evaluate`](crate::evaluate) or
+/// [`from_str`](crate::from_str).
+///
+/// # Arguments
+///
+/// * `value` - The value to serialize
+///
+/// # Returns
+///
+/// * `Ok(String)` - The RESL source text
+/// * `Err(SerializeError)` - If the value cannot be represented in RESL
+///
+/// # Examples
+///
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let server = Server {
+///     host: "localhost".to_string(),
+///     port: 8080,
+/// };
+///
+/// let output = resl::to_string(&server).unwrap();
+/// assert_eq!(output, r#"["host": "localhost", "port": 8080]"#);
+/// ```
+pub fn to_string<T: ?Sized + Serialize>(value: &T) -> Result<String, SerializeError> {
+    write_value(value, false)
+}
+
+/// Serializes a value into pretty-printed RESL source text.
+///
+/// The output uses the same layout as [`Value::write_formatted`] with
+/// pretty-printing, placing every list element and map entry on its own line.
+///
+/// # Arguments
+///
+/// * `value` - The value to serialize
+///
+/// # Returns
+///
+/// * `Ok(String)` - The RESL source text
+/// * `Err(SerializeError)` - If the value cannot be represented in RESL
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// let ports = BTreeMap::from([("http", 80), ("https", 443)]);
+///
+/// let output = resl::to_string_pretty(&ports).unwrap();
+/// assert_eq!(output, "[\n    \"http\": 80,\n    \"https\": 443\n]");
+/// ```
+pub fn to_string_pretty<T: ?Sized + Serialize>(value: &T) -> Result<String, SerializeError> {
+    write_value(value, true)
+}
+
+/// Serializes a value into a RESL [`Value`].
+///
+/// Structs and maps serialize into maps, sequences and tuples into lists, unit
+/// enum variants into strings and `None` or `()` into null. Other enum variants
+/// serialize into a map with a single entry keyed by the variant name, which is
+/// the representation expected by [`from_value`](crate::from_value).
+///
+/// # Arguments
+///
+/// * `value` - The value to serialize
+///
+/// # Returns
+///
+/// * `Ok(Value)` - The serialized value
+/// * `Err(SerializeError)` - If the value cannot be represented in RESL
+///
+/// # Examples
+///
+/// ```
+/// use resl::{Value, to_value};
+///
+/// assert_eq!(to_value(&Some(5)).unwrap(), Value::Integer(5));
+/// assert_eq!(to_value(&None::<i32>).unwrap(), Value::Null);
+///
+/// // Integers beyond the range of a 64-bit signed integer are rejected
+/// assert!(to_value(&u64::MAX).is_err());
+/// ```
+pub fn to_value<T: ?Sized + Serialize>(value: &T) -> Result<Value, SerializeError> {
+    value.serialize(ValueSerializer)
+}
+
+fn write_value<T: ?Sized + Serialize>(value: &T, pretty: bool) -> Result<String, SerializeError> {
+    let mut output = String::new();
+    to_value(value)?
+        .write_formatted(&mut output, pretty)
+        .map_err(ser::Error::custom)?;
+
+    Ok(output)
+}
+
+/// Serializer producing owned values.
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Error = SerializeError;
+    type Ok = Value;
+    type SerializeMap = SerializeMap;
+    type SerializeSeq = SerializeList;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeStructVariant;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeTupleVariant;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Self::Error> {
+        Ok(Value::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Self::Error> {
+        Ok(Value::Integer(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value, Self::Error> {
+        integer(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, Self::Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Self::Error> {
+        Ok(Value::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Self::Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Self::Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Self::Error> {
+        Ok(Value::List(
+            v.iter()
+                .map(|byte| Value::Integer((*byte).into()))
+                .collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Value, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Self::Error> {
+        Ok(Value::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Self::Error> {
+        Ok(variant_map(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(SerializeList {
+            list: ValueList::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(SerializeTupleVariant {
+            variant,
+            list: ValueList::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(SerializeMap {
+            map: ValueMap::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(SerializeStructVariant {
+            variant,
+            map: ValueMap::new(),
+        })
+    }
+}
+
+/// Collects the elements of sequences and tuples into a list.
+struct SerializeList {
+    list: ValueList,
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Error = SerializeError;
+    type Ok = Value;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.list.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        Ok(Value::List(self.list))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Error = SerializeError;
+    type Ok = Value;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Error = SerializeError;
+    type Ok = Value;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Collects the fields of a tuple variant into a list keyed by the variant name.
+struct SerializeTupleVariant {
+    variant: &'static str,
+    list: ValueList,
+}
+
+impl ser::SerializeTupleVariant for SerializeTupleVariant {
+    type Error = SerializeError;
+    type Ok = Value;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.list.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        Ok(variant_map(self.variant, Value::List(self.list)))
+    }
+}
+
+/// Collects the entries of maps and structs into a map.
+struct SerializeMap {
+    map: ValueMap,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Error = SerializeError;
+    type Ok = Value;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.key = Some(key.serialize(MapKeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| ser::Error::custom("map value serialized before its key"))?;
+        self.map.insert(key, to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        Ok(Value::Map(self.map))
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Error = SerializeError;
+    type Ok = Value;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.map.insert(key.to_string(), to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Collects the fields of a struct variant into a map keyed by the variant name.
+struct SerializeStructVariant {
+    variant: &'static str,
+    map: ValueMap,
+}
+
+impl ser::SerializeStructVariant for SerializeStructVariant {
+    type Error = SerializeError;
+    type Ok = Value;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.map.insert(key.to_string(), to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        Ok(variant_map(self.variant, Value::Map(self.map)))
+    }
+}
+
+/// Serializer for map keys, which must be strings.
+///
+/// Characters, booleans and integers are converted to their string form.
+struct MapKeySerializer;
+
+impl ser::Serializer for MapKeySerializer {
+    type Error = SerializeError;
+    type Ok = String;
+    type SerializeMap = ser::Impossible<String, SerializeError>;
+    type SerializeSeq = ser::Impossible<String, SerializeError>;
+    type SerializeStruct = ser::Impossible<String, SerializeError>;
+    type SerializeStructVariant = ser::Impossible<String, SerializeError>;
+    type SerializeTuple = ser::Impossible<String, SerializeError>;
+    type SerializeTupleStruct = ser::Impossible<String, SerializeError>;
+    type SerializeTupleVariant = ser::Impossible<String, SerializeError>;
+
+    fn serialize_bool(self, v: bool) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<String, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<String, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<String, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, Self::Error> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+}
+
+/// Converts an integer to a value, rejecting integers outside the range of `i64`.
+fn integer<I: TryInto<i64> + std::fmt::Display + Copy>(v: I) -> Result<Value, SerializeError> {
+    v.try_into().map(Value::Integer).map_err(|_| {
+        ser::Error::custom(format!(
+            "integer {v} is out of range for a 64-bit signed integer"
+        ))
+    })
+}
+
+/// Wraps the content of an enum variant in a map keyed by the variant name.
+fn variant_map(variant: &'static str, value: Value) -> Value {
+    let mut map = ValueMap::new();
+    map.insert(variant.to_string(), value);
+    Value::Map(map)
+}
+
+fn key_must_be_a_string() -> SerializeError {
+    ser::Error::custom("map key must be a string")
+}
@@ -1,18 +1,42 @@
+use std::{collections::BTreeMap, sync::Arc};
+
 use string_interner::{StringInterner, backend::StringBackend, symbol::SymbolU32};
 
-use crate::{binding::Binding, context::Context, expr::Expr, ident::Ident};
+use crate::{
+    binding::Binding,
+    comment::{Comment, Comments, Pending},
+    context::Context,
+    document::{Name, NameKind},
+    engine::Engine,
+    env::{ClosureCache, Env},
+    error::{EvalResult, RuntimeError, RuntimeErrorKind},
+    ident::Ident,
+    module::Modules,
+    span::{Span, Spanned},
+    value::Value,
+};
 
 pub(crate) type Interner = StringInterner<StringBackend>;
 
+/// Maximum number of nested function calls, so that runaway recursion fails
+/// instead of overflowing the stack.
+const MAX_CALL_DEPTH: usize = 128;
+
 /// Manages all evaluation contexts and string interning.
 ///
 /// CtxState holds the global state for RESL evaluation, including all variable
 /// binding contexts organized in a hierarchical structure and a string interner
-/// for efficient identifier storage.
+/// for efficient identifier storage. Comments found while parsing are kept
+/// so that formatting can preserve them, and declared names are kept along with
+/// their spans for tooling. The parsed source is kept to locate errors raised by
+/// closures called from other modules.
 #[derive(Debug)]
 pub struct CtxState {
     contexts: Vec<Context>,
     interner: Interner,
+    comments: Comments,
+    names: BTreeMap<usize, Name>,
+    source: Arc<str>,
 }
 
 impl CtxState {
@@ -22,9 +46,60 @@ impl CtxState {
         Self {
             contexts: vec![Context::root(&mut interner)],
             interner,
+            comments: Comments::new(),
+            names: BTreeMap::new(),
+            source: Arc::from(""),
         }
     }
 
+    /// Gets the source text the contexts were parsed from.
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Gets the source text within `span`, if it lies within the source.
+    pub(crate) fn source_text(&self, span: Span) -> Option<&str> {
+        self.source.get(span.start()..span.end())
+    }
+
+    /// Sets the source text the contexts are parsed from.
+    pub(crate) fn set_source(&mut self, source: &str) {
+        self.source = Arc::from(source);
+    }
+
+    /// Gets the comments found while parsing.
+    pub(crate) fn comments(&self) -> &Comments {
+        &self.comments
+    }
+
+    /// Records a comment starting at `offset`.
+    ///
+    /// Comments may be skipped again after backtracking, so an existing record is kept.
+    pub(crate) fn record_comment(&mut self, offset: usize, comment: Comment) {
+        self.comments.entry(offset).or_insert(comment);
+    }
+
+    /// Gets the declared names found while parsing, in source order.
+    pub(crate) fn names(&self) -> impl Iterator<Item = &Name> {
+        self.names.values()
+    }
+
+    /// Records a name declared at `span`.
+    ///
+    /// Like comments, names may be parsed again after backtracking, so an existing
+    /// record is kept.
+    pub(crate) fn record_name(&mut self, text: String, kind: NameKind, span: Span) {
+        self.names
+            .entry(span.start())
+            .or_insert(Name { text, kind, span });
+    }
+
+    /// Binds a name in the root context, replacing any existing binding.
+    pub(crate) fn bind_root(&mut self, name: &str, binding: Binding) {
+        let ident = Ident::using_interner(name, &mut self.interner);
+        self.contexts[0].insert(ident, binding);
+    }
+
     /// Places a context at the specified index, resizing the context vector if needed.
     pub(crate) fn place_ctx(&mut self, ctx_idx: usize, ctx: Context) {
         if ctx_idx >= self.len() {
@@ -33,20 +108,11 @@ impl CtxState {
         self[ctx_idx] = ctx;
     }
 
-    /// Finds the context index that contains the specified identifier.
-    pub(crate) fn find_ctx_with_ident(
-        &self,
-        current_ctx_idx: usize,
-        ident: &Ident,
-    ) -> Option<usize> {
-        let mut ctx_idx = current_ctx_idx;
-        loop {
-            let ctx = &self[ctx_idx];
-            if ctx.contains_key(ident) {
-                return Some(ctx_idx);
-            }
-            ctx_idx = ctx.parent_ctx_idx()?;
-        }
+    /// Resolves an identifier to its string representation.
+    pub(crate) fn resolve_ident(&self, ident: &Ident) -> &str {
+        self.interner
+            .resolve(ident.to_symbol())
+            .expect("Identifier not found in interner")
     }
 }
 
@@ -79,6 +145,7 @@ impl std::ops::DerefMut for CtxState {
 pub struct ParseState<'ctx> {
     active_ctx_idx: usize,
     avail_ctx_idx: usize,
+    bitwise_or: bool,
     ctx_state: &'ctx mut CtxState,
 }
 
@@ -89,6 +156,7 @@ impl<'ctx> ParseState<'ctx> {
         Self {
             active_ctx_idx: ctx_state_len - 1,
             avail_ctx_idx: ctx_state_len,
+            bitwise_or: true,
             ctx_state,
         }
     }
@@ -118,6 +186,24 @@ impl<'ctx> ParseState<'ctx> {
         self.avail_ctx_idx -= 1;
     }
 
+    /// Returns whether `|` is parsed as the bitwise or operator.
+    ///
+    /// It is not inside the then branch of a conditional, where `|` starts the else branch.
+    pub(crate) fn bitwise_or(&self) -> bool {
+        self.bitwise_or
+    }
+
+    /// Sets whether `|` is parsed as the bitwise or operator, returning the previous setting.
+    pub(crate) fn set_bitwise_or(&mut self, enabled: bool) -> bool {
+        std::mem::replace(&mut self.bitwise_or, enabled)
+    }
+
+    /// Records an identifier declared as a name of the given kind.
+    pub(crate) fn record_ident(&mut self, ident: &Spanned<Ident>, kind: NameKind) {
+        let text = self.ctx_state.resolve_ident(&ident.node).to_string();
+        self.ctx_state.record_name(text, kind, ident.span);
+    }
+
     /// Interns a string and returns its symbol identifier.
     pub(crate) fn get_interned(&mut self, s: &str) -> SymbolU32 {
         self.ctx_state.interner.get_or_intern(s)
@@ -140,47 +226,151 @@ impl std::ops::DerefMut for ParseState<'_> {
 
 /// State manager for expression evaluation.
 ///
-/// EvalState manages the active context during expression evaluation and
-/// provides access to variable bindings and context switching for function
-/// calls and block evaluation.
+/// EvalState manages the active environment during expression evaluation and
+/// provides access to variable bindings and environment switching for function
+/// calls and block evaluation. In strict mode, runtime errors abort evaluation
+/// instead of producing null values. Modules imported while evaluating are
+/// shared with the evaluation states of those modules.
 #[derive(Debug)]
 pub struct EvalState<'ctx> {
-    active_ctx_idx: usize,
-    ctx_state: &'ctx mut CtxState,
+    env: Arc<Env>,
+    /// Number of function calls currently being evaluated
+    call_depth: usize,
+    strict: bool,
+    ctx_state: Arc<CtxState>,
+    engine: &'ctx Engine,
+    modules: &'ctx mut Modules,
+    closure_cache: &'ctx mut ClosureCache,
 }
 
 impl<'ctx> EvalState<'ctx> {
     /// Creates a new evaluation state starting from the root context.
-    pub(crate) fn new(ctx_state: &'ctx mut CtxState) -> Self {
+    pub(crate) fn new(
+        ctx_state: Arc<CtxState>,
+        engine: &'ctx Engine,
+        modules: &'ctx mut Modules,
+        closure_cache: &'ctx mut ClosureCache,
+    ) -> Self {
         Self {
-            active_ctx_idx: 0,
+            env: Env::root(),
+            call_depth: 0,
+            strict: engine.is_strict(),
             ctx_state,
+            engine,
+            modules,
+            closure_cache,
         }
     }
 
-    /// Gets the current active context index.
-    pub(crate) fn active_ctx_idx(&self) -> usize {
-        self.active_ctx_idx
+    /// Gets the engine the evaluation was started from.
+    pub(crate) fn engine(&self) -> &'ctx Engine {
+        self.engine
     }
 
-    /// Sets the active context for evaluation operations.
-    pub(crate) fn set_active_ctx(&mut self, ctx_idx: usize) {
-        self.active_ctx_idx = ctx_idx;
+    /// Gets the modules imported during the evaluation.
+    pub(crate) fn modules(&mut self) -> &mut Modules {
+        self.modules
     }
 
-    /// Finds the context that contains the specified identifier.
-    pub(crate) fn find_ctx_with_ident(&self, ident: &Ident) -> Option<usize> {
-        self.ctx_state
-            .find_ctx_with_ident(self.active_ctx_idx, ident)
+    /// Gets the bindings caching closures during the evaluation.
+    pub(crate) fn closure_cache(&mut self) -> &mut ClosureCache {
+        self.closure_cache
+    }
+
+    /// Gets the modules and the closure cache together, for evaluating another module.
+    pub(crate) fn shared(&mut self) -> (&mut Modules, &mut ClosureCache) {
+        (self.modules, self.closure_cache)
+    }
+
+    /// Reports a runtime error for the expression at `span`.
+    ///
+    /// Returns the error in strict mode, otherwise the failing expression evaluates to null.
+    pub(crate) fn raise(&self, kind: RuntimeErrorKind, span: Span) -> EvalResult {
+        if self.strict {
+            Err(RuntimeError::new(kind, span))
+        } else {
+            Ok(Value::Null)
+        }
+    }
+
+    /// Gets the contexts of the module being evaluated.
+    pub(crate) fn ctx_state(&self) -> &Arc<CtxState> {
+        &self.ctx_state
+    }
+
+    /// Evaluates `f` with the contexts of `ctx_state`, restoring the previous ones afterwards.
+    ///
+    /// Closures are called with the contexts they were parsed into, which differ from
+    /// the active ones if the closure was imported from another module.
+    pub(crate) fn with_ctx_state<T>(
+        &mut self,
+        ctx_state: Arc<CtxState>,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let previous = std::mem::replace(&mut self.ctx_state, ctx_state);
+        let result = f(self);
+        self.ctx_state = previous;
+        result
+    }
+
+    /// Gets the active environment.
+    pub(crate) fn env(&self) -> &Arc<Env> {
+        &self.env
+    }
+
+    /// Evaluates `f` with `env` as the active environment, restoring the previous one afterwards.
+    pub(crate) fn with_env<T>(&mut self, env: Arc<Env>, f: impl FnOnce(&mut Self) -> T) -> T {
+        let previous = std::mem::replace(&mut self.env, env);
+        let result = f(self);
+        self.env = previous;
+        result
+    }
+
+    /// Evaluates `f` as the body of a function call, failing if calls are nested too deeply.
+    pub(crate) fn with_call(
+        &mut self,
+        span: Span,
+        f: impl FnOnce(&mut Self) -> EvalResult,
+    ) -> EvalResult {
+        if self.call_depth >= MAX_CALL_DEPTH {
+            return self.raise(RuntimeErrorKind::CallDepthExceeded(MAX_CALL_DEPTH), span);
+        }
+
+        self.call_depth += 1;
+        let result = f(self);
+        self.call_depth -= 1;
+        result
+    }
+
+    /// Creates an environment for the context at `ctx_idx` within `parent`, binding
+    /// the given parameters to their values.
+    ///
+    /// Bindings of the context without a value are evaluated from their expression
+    /// when first looked up.
+    pub(crate) fn new_env(
+        &self,
+        ctx_idx: usize,
+        parent: Arc<Env>,
+        params: impl IntoIterator<Item = (Ident, Value)>,
+    ) -> Arc<Env> {
+        debug_assert_eq!(
+            self[ctx_idx].parent_ctx_idx(),
+            Some(parent.ctx_idx()),
+            "Environments are nested like their contexts"
+        );
+
+        Env::new(ctx_idx, Some(parent), params)
     }
 
-    /// Gets the expression bound to an identifier, if it exists.
-    pub(crate) fn get_expr<'a>(&'a mut self, ident: &'a Ident) -> Option<&'a Expr> {
-        let ctx_idx = self.find_ctx_with_ident(ident)?;
-        if let Some(Binding::Expr(expr)) = self[ctx_idx].get(ident) {
-            return Some(expr);
+    /// Finds the environment that binds the specified identifier.
+    pub(crate) fn find_env_with_ident(&self, ident: &Ident) -> Option<Arc<Env>> {
+        let mut env = &self.env;
+        loop {
+            if self[env.ctx_idx()].contains_key(ident) {
+                return Some(env.clone());
+            }
+            env = env.parent()?;
         }
-        None
     }
 }
 
@@ -188,13 +378,7 @@ impl std::ops::Deref for EvalState<'_> {
     type Target = CtxState;
 
     fn deref(&self) -> &Self::Target {
-        self.ctx_state
-    }
-}
-
-impl std::ops::DerefMut for EvalState<'_> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.ctx_state
+        &self.ctx_state
     }
 }
 
@@ -208,15 +392,18 @@ pub struct FmtState<'ctx> {
     pretty: bool,
     indent_level: usize,
     ctx_state: &'ctx CtxState,
+    /// Comments left to write
+    pending: &'ctx Pending,
 }
 
 impl<'ctx> FmtState<'ctx> {
     /// Creates a new format state with the specified pretty-printing mode.
-    pub(crate) fn new(pretty: bool, ctx_state: &'ctx CtxState) -> Self {
+    pub(crate) fn new(pretty: bool, ctx_state: &'ctx CtxState, pending: &'ctx Pending) -> Self {
         Self {
             pretty,
             indent_level: 0,
             ctx_state,
+            pending,
         }
     }
 
@@ -226,6 +413,17 @@ impl<'ctx> FmtState<'ctx> {
             pretty: self.pretty,
             indent_level: self.indent_level + 1,
             ctx_state: self.ctx_state,
+            pending: self.pending,
+        }
+    }
+
+    /// Creates a new format state that writes everything on a single line.
+    pub(crate) fn compact(&self) -> Self {
+        Self {
+            pretty: false,
+            indent_level: self.indent_level,
+            ctx_state: self.ctx_state,
+            pending: self.pending,
         }
     }
 
@@ -239,12 +437,14 @@ impl<'ctx> FmtState<'ctx> {
         self.indent_level
     }
 
-    /// Resolves an identifier to its string representation.
-    pub(crate) fn resolve_ident(&self, ident: &Ident) -> &str {
+    /// Gets the context state the expressions are formatted from.
+    pub(crate) fn ctx_state(&self) -> &'ctx CtxState {
         self.ctx_state
-            .interner
-            .resolve(ident.to_symbol())
-            .expect("Identifier not found in interner")
+    }
+
+    /// Gets the offsets of the comments left to write.
+    pub(crate) fn pending(&self) -> &'ctx Pending {
+        self.pending
     }
 }
 
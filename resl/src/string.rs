@@ -1,31 +1,220 @@
 use winnow::{
     ModalResult, Parser,
-    ascii::escaped,
-    combinator::{alt, cut_err, delimited, opt},
-    token::take_while,
+    ascii::{line_ending, space0},
+    combinator::{alt, cut_err, delimited, fail, not, opt, preceded, repeat, terminated},
+    stream::Stream,
+    token::{literal, take_until, take_while},
 };
 
 use crate::{
-    StatefulInput,
-    expr::Expr,
-    macros::{exp_char, label},
+    StatefulInput, comment,
+    error::EvalResult,
+    expr::{Expr, ExprKind},
+    macros::{exp_char, exp_desc, exp_str, label},
+    span::Span,
+    state::{EvalState, FmtState},
+    utils::{spanned, with_bitwise_or},
+    value::Value,
 };
 
+/// String literal with embedded `${expr}` expressions.
+#[derive(Debug, Clone)]
+pub struct Interpolation(Vec<Segment>);
+
+/// Part of a string literal.
+#[derive(Debug, Clone)]
+enum Segment {
+    Text(String),
+    Expr(Expr),
+}
+
 pub(crate) fn parse(input: &mut StatefulInput) -> ModalResult<Expr> {
-    delimited(
-        '"',
-        // Parse the content of the string, allowing for escaped characters
-        opt(escaped(
-            take_while(1.., |c: char| !['\\', '\"', '\n'].contains(&c)),
-            '\\',
-            alt(('\\'.value("\\"), '"'.value("\""), '\n'.value("\n"))),
+    spanned(
+        alt((
+            parse_raw.map(ExprKind::Str),
+            parse_multiline.map(into_kind),
+            delimited(
+                '"',
+                repeat(0.., parse_segment).fold(Vec::new, push_segment),
+                cut_err('"').context(exp_char!('"')),
+            )
+            .map(into_kind),
         ))
-        // If there are no characters between the quotes, return an empty string
-        .map(Option::unwrap_or_default),
-        cut_err('"').context(exp_char!('"')),
+        .context(label!("string")),
+    )
+    .parse_next(input)
+}
+
+/// Parses a string literal without embedded expressions, as used for map keys.
+///
+/// Unlike [`parse`], an unterminated quoted literal is not an error, so that the
+/// caller can backtrack.
+pub(crate) fn parse_literal(input: &mut StatefulInput) -> ModalResult<String> {
+    alt((
+        parse_raw,
+        delimited(
+            '"',
+            repeat(0.., parse_text).fold(String::new, |mut string, text| {
+                string.push_str(&text);
+                string
+            }),
+            '"',
+        ),
+    ))
+    .parse_next(input)
+}
+
+/// Appends a segment, merging adjacent text so plain strings end up as a single segment.
+fn push_segment(mut segments: Vec<Segment>, segment: Segment) -> Vec<Segment> {
+    match (segments.last_mut(), segment) {
+        (Some(Segment::Text(text)), Segment::Text(next)) => text.push_str(&next),
+        (_, segment) => segments.push(segment),
+    }
+    segments
+}
+
+fn into_kind(mut segments: Vec<Segment>) -> ExprKind {
+    match segments.as_mut_slice() {
+        [] => ExprKind::Str(String::new()),
+        [Segment::Text(text)] => ExprKind::Str(std::mem::take(text)),
+        _ => ExprKind::Interpolated(Interpolation(segments)),
+    }
+}
+
+fn parse_segment(input: &mut StatefulInput) -> ModalResult<Segment> {
+    alt((
+        parse_text.map(Segment::Text),
+        parse_embedded.map(Segment::Expr),
+    ))
+    .parse_next(input)
+}
+
+fn parse_embedded(input: &mut StatefulInput) -> ModalResult<Expr> {
+    delimited(
+        "${",
+        with_bitwise_or(true, Expr::require_parse),
+        cut_err('}').context(exp_char!('}')),
+    )
+    .parse_next(input)
+}
+
+/// Parses a run of characters or an escaped character, stopping before `${`.
+fn parse_text(input: &mut StatefulInput) -> ModalResult<String> {
+    alt((
+        take_while(1.., |c: char| !['\\', '\"', '\n', '$'].contains(&c)).map(str::to_string),
+        parse_escape.map(String::from),
+        // A `$` not starting an embedded expression
+        terminated("$", not('{')).map(str::to_string),
+    ))
+    .parse_next(input)
+}
+
+/// Parses an escape sequence, including the leading backslash.
+fn parse_escape(input: &mut StatefulInput) -> ModalResult<char> {
+    preceded(
+        '\\',
+        cut_err(alt((
+            '\\'.value('\\'),
+            '"'.value('"'),
+            '$'.value('$'),
+            'n'.value('\n'),
+            't'.value('\t'),
+            'r'.value('\r'),
+            '0'.value('\0'),
+            // An escaped line break is kept
+            '\n'.value('\n'),
+            delimited(
+                "u{",
+                take_while(1..=6, |c: char| c.is_ascii_hexdigit()),
+                '}',
+            )
+            .verify_map(|hex: &str| u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)),
+        )))
+        .context(exp_desc!("escape sequence")),
+    )
+    .parse_next(input)
+}
+
+/// Parses a raw string such as `r"C:\path"` or `r#"say "hi""#`, which has no escapes
+/// or embedded expressions and may span lines.
+fn parse_raw(input: &mut StatefulInput) -> ModalResult<String> {
+    let hashes = terminated(preceded('r', take_while(0.., '#')), '"').parse_next(input)?;
+    let closing = format!("\"{hashes}");
+
+    cut_err(terminated(
+        take_until(0.., closing.as_str()),
+        closing.as_str(),
+    ))
+    .context(exp_str!("closing delimiter of raw string"))
+    .map(str::to_string)
+    .parse_next(input)
+}
+
+/// Parses a multi-line string between lines of `"""`.
+///
+/// The content starts on the line after the opening `"""`, and the indentation of
+/// the closing `"""` is removed from every line. The line break before the closing
+/// `"""` is not part of the string. Quotes need no escaping.
+fn parse_multiline(input: &mut StatefulInput) -> ModalResult<Vec<Segment>> {
+    (
+        "\"\"\"",
+        space0,
+        cut_err(line_ending).context(exp_desc!("line break")),
     )
-    .context(label!("string"))
-    .map(Expr::Str)
+        .parse_next(input)?;
+
+    // The first line starting with `"""` closes the string and sets the indentation
+    let remaining = input.input.peek_finish();
+    let Some(indent) = remaining.lines().find_map(|line| {
+        let content = line.trim_start();
+        content
+            .starts_with("\"\"\"")
+            .then(|| &line[..line.len() - content.len()])
+    }) else {
+        return cut_err(fail).context(exp_str!("\"\"\"")).parse_next(input);
+    };
+    let indent = indent.to_string();
+
+    let mut segments = Vec::new();
+    let mut first = true;
+
+    loop {
+        // Lines with only whitespace may be indented less than the closing delimiter
+        let blank = opt(terminated(space0, line_ending)).parse_next(input)?;
+        if blank.is_none() {
+            cut_err(literal(indent.as_str()))
+                .context(exp_desc!("indentation of the closing delimiter"))
+                .parse_next(input)?;
+
+            if opt("\"\"\"").parse_next(input)?.is_some() {
+                return Ok(segments);
+            }
+        }
+
+        if !first {
+            segments = push_segment(segments, Segment::Text("\n".to_string()));
+        }
+        first = false;
+
+        if blank.is_none() {
+            while let Some(segment) = opt(parse_multiline_segment).parse_next(input)? {
+                segments = push_segment(segments, segment);
+            }
+            cut_err(line_ending)
+                .context(exp_desc!("line break"))
+                .parse_next(input)?;
+        }
+    }
+}
+
+fn parse_multiline_segment(input: &mut StatefulInput) -> ModalResult<Segment> {
+    alt((
+        take_while(1.., |c: char| !['\\', '\r', '\n', '$'].contains(&c))
+            .map(|text: &str| Segment::Text(text.to_string())),
+        parse_escape.map(|c| Segment::Text(c.to_string())),
+        parse_embedded.map(Segment::Expr),
+        terminated("$", not('{')).map(|text: &str| Segment::Text(text.to_string())),
+    ))
     .parse_next(input)
 }
 
@@ -37,3 +226,109 @@ pub(crate) fn parse_plain<'input>(
     })
     .parse_next(input)
 }
+
+/// Writes the string literal spanning `span` as written in the source if it is a raw
+/// or multi-line string, or as an escaped literal otherwise.
+pub(crate) fn format_literal<W: std::fmt::Write>(
+    writer: &mut W,
+    state: FmtState,
+    span: Span,
+    s: &str,
+) -> std::fmt::Result {
+    match verbatim(state, span) {
+        Some(text) => writer.write_str(text),
+        None => write_literal(writer, s),
+    }
+}
+
+/// Gets the source text of a raw or multi-line string literal spanning `span`, whose
+/// form would be lost by escaping it onto a single line.
+fn verbatim(state: FmtState<'_>, span: Span) -> Option<&str> {
+    state.ctx_state().source_text(span).filter(|text| {
+        ["r\"", "r#", "\"\"\""]
+            .iter()
+            .any(|start| text.starts_with(start))
+    })
+}
+
+/// Writes a string as a literal, escaping the characters that would end it or start
+/// an embedded expression.
+pub(crate) fn write_literal<W: std::fmt::Write>(writer: &mut W, s: &str) -> std::fmt::Result {
+    write!(writer, "\"")?;
+    write_escaped(writer, s)?;
+    write!(writer, "\"")
+}
+
+fn write_escaped<W: std::fmt::Write>(writer: &mut W, s: &str) -> std::fmt::Result {
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' | '"' => write!(writer, "\\{c}")?,
+            '\n' => write!(writer, "\\n")?,
+            '\t' => write!(writer, "\\t")?,
+            '\r' => write!(writer, "\\r")?,
+            '\0' => write!(writer, "\\0")?,
+            c if c.is_control() => write!(writer, "\\u{{{:x}}}", c as u32)?,
+            '$' if chars.peek() == Some(&'{') => write!(writer, "\\$")?,
+            c => write!(writer, "{c}")?,
+        }
+    }
+
+    Ok(())
+}
+
+impl Interpolation {
+    pub(crate) fn evaluate(self, state: &mut EvalState) -> EvalResult {
+        let mut string = String::new();
+
+        for segment in self.0 {
+            match segment {
+                Segment::Text(text) => string.push_str(&text),
+                Segment::Expr(expr) => string.push_str(&expr.evaluate(state)?.stringify()),
+            }
+        }
+
+        Ok(Value::String(string))
+    }
+
+    /// Returns the embedded expressions.
+    pub(crate) fn children(&self) -> Vec<&Expr> {
+        self.0
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Text(_) => None,
+                Segment::Expr(expr) => Some(expr),
+            })
+            .collect()
+    }
+
+    pub(crate) fn format<W: std::fmt::Write>(
+        &self,
+        span: Span,
+        writer: &mut W,
+        state: FmtState,
+    ) -> std::fmt::Result {
+        // Multi-line strings are kept as written, along with the comments in them
+        if let Some(text) = verbatim(state, span) {
+            comment::skip_pending(state, span.end());
+            return writer.write_str(text);
+        }
+
+        write!(writer, "\"")?;
+
+        for segment in &self.0 {
+            match segment {
+                Segment::Text(text) => write_escaped(writer, text)?,
+                Segment::Expr(expr) => {
+                    // Embedded expressions stay on the line of the string
+                    write!(writer, "${{")?;
+                    expr.format(writer, state.compact())?;
+                    write!(writer, "}}")?;
+                }
+            }
+        }
+
+        write!(writer, "\"")
+    }
+}
@@ -1,13 +1,59 @@
-use winnow::{Parser, ascii::multispace0, combinator::delimited};
+use winnow::{
+    Parser,
+    combinator::delimited,
+    error::{ContextError, ErrMode},
+};
 
+use crate::{
+    StatefulInput, comment,
+    expr::{Expr, ExprKind},
+    span::Spanned,
+};
+
+/// Wraps a parser so that whitespace and comments around it are skipped.
+#[inline(always)]
+pub(crate) fn delimited_multispace0<'input, 'state, O>(
+    parser: impl Parser<StatefulInput<'input, 'state>, O, ErrMode<ContextError>>,
+) -> impl Parser<StatefulInput<'input, 'state>, O, ErrMode<ContextError>> {
+    delimited(comment::skip, parser, comment::skip)
+}
+
+/// Wraps a parser so that `|` is or isn't parsed as the bitwise or operator within it.
+///
+/// Conditionals disable it in their then branch, and brackets enable it again.
+#[inline(always)]
+pub(crate) fn with_bitwise_or<'input, 'state, O>(
+    enabled: bool,
+    mut parser: impl Parser<StatefulInput<'input, 'state>, O, ErrMode<ContextError>>,
+) -> impl Parser<StatefulInput<'input, 'state>, O, ErrMode<ContextError>> {
+    move |input: &mut StatefulInput<'input, 'state>| {
+        let previous = input.state.set_bitwise_or(enabled);
+        let result = parser.parse_next(input);
+        input.state.set_bitwise_or(previous);
+        result
+    }
+}
+
+/// Wraps a parser of an expression kind so that it produces an [`Expr`]
+/// spanning all the input consumed by the parser.
+#[inline(always)]
+pub(crate) fn spanned<'input, 'state>(
+    parser: impl Parser<StatefulInput<'input, 'state>, ExprKind, ErrMode<ContextError>>,
+) -> impl Parser<StatefulInput<'input, 'state>, Expr, ErrMode<ContextError>> {
+    parser
+        .with_span()
+        .map(|(kind, range)| Expr::new(kind, range.into()))
+}
+
+/// Wraps a parser so that its output is paired with the span of input it consumed.
 #[inline(always)]
-pub(crate) fn delimited_multispace0<I, O, E>(parser: impl Parser<I, O, E>) -> impl Parser<I, O, E>
-where
-    I: winnow::stream::StreamIsPartial + winnow::stream::Stream,
-    <I as winnow::stream::Stream>::Token: winnow::stream::AsChar + Clone,
-    E: winnow::error::ParserError<I>,
-{
-    delimited(multispace0, parser, multispace0)
+pub(crate) fn located<'input, 'state, O>(
+    parser: impl Parser<StatefulInput<'input, 'state>, O, ErrMode<ContextError>>,
+) -> impl Parser<StatefulInput<'input, 'state>, Spanned<O>, ErrMode<ContextError>> {
+    parser.with_span().map(|(node, range)| Spanned {
+        node,
+        span: range.into(),
+    })
 }
 
 pub(crate) fn write_indent<W: std::fmt::Write>(
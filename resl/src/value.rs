@@ -1,6 +1,10 @@
+use std::cmp::Ordering;
+
 use serde::{Deserialize, Serialize};
 
-use crate::utils::write_indent;
+use crate::{
+    error::RuntimeErrorKind, function::closure::Closure, number, string, utils::write_indent,
+};
 
 pub(crate) type ValueList = Vec<Value>;
 
@@ -48,7 +52,10 @@ pub(crate) type ValueMap = indexmap::IndexMap<String, Value>;
 /// let result6 = evaluate("[\"name\": \"Alice\", \"age\": 30]").unwrap();
 /// // This produces a Value::Map containing the key-value pairs
 /// ```
+///
+/// New kinds of values may be added, so matching on a `Value` needs a wildcard arm.
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum Value {
     /// The null output value, representing the absence of meaningful data.
     ///
@@ -148,6 +155,22 @@ pub enum Value {
     /// ["data": ["nested": "value"]]
     /// ```
     Map(ValueMap),
+    /// A function output value that can be called with arguments.
+    ///
+    /// This is produced by function declarations and by names of functions,
+    /// including built-in ones. Functions have no data representation, so they
+    /// are written as `<function>`, are never equal to any value and cannot be
+    /// serialized.
+    ///
+    /// ### RESL Expressions that produce Function
+    ///
+    /// ```resl
+    /// |x| x * 2
+    /// length
+    /// { factor = 3; |x| x * factor }
+    /// ```
+    #[serde(skip)]
+    Function(Closure),
 }
 
 impl Value {
@@ -169,15 +192,9 @@ impl Value {
     ) -> std::fmt::Result {
         match self {
             Value::Null => write!(writer, "null"),
-            Value::String(s) => write!(writer, "\"{}\"", s),
+            Value::String(s) => string::write_literal(writer, s),
             Value::Integer(i) => write!(writer, "{}", i),
-            Value::Float(f) => {
-                if f.fract() == 0.0 {
-                    write!(writer, "{:.1}", f)
-                } else {
-                    write!(writer, "{f}")
-                }
-            }
+            Value::Float(f) => number::write_float(writer, *f),
             Value::Boolean(b) => write!(writer, "{}", b),
             Value::List(l) => {
                 write!(writer, "[")?;
@@ -230,7 +247,8 @@ impl Value {
                 let mut map_iter = m.iter().peekable();
 
                 while let Some((key, value)) = map_iter.next() {
-                    write!(writer, "\"{key}\": ")?;
+                    string::write_literal(writer, key)?;
+                    write!(writer, ": ")?;
                     value.format(writer, pretty, indent_level + 1)?;
                     if map_iter.peek().is_some() {
                         write!(writer, ",")?;
@@ -250,6 +268,7 @@ impl Value {
 
                 write!(writer, "]")
             }
+            Value::Function(_) => write!(writer, "<function>"),
         }
     }
 
@@ -280,6 +299,131 @@ impl Value {
         self.format(writer, pretty, 0)
     }
 
+    /// Returns the name of this value's type, as reported by the `type_of` built-in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use resl::Value;
+    ///
+    /// assert_eq!(Value::Integer(42).type_name(), "integer");
+    /// ```
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Boolean(_) => "boolean",
+            Value::Integer(_) => "integer",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+            Value::Function(_) => "function",
+        }
+    }
+
+    /// Converts the value to a string, as done by `to_str` and string interpolation.
+    ///
+    /// Strings are returned as they are, other values are written as RESL source.
+    pub(crate) fn stringify(self) -> String {
+        match self {
+            Value::String(s) => s,
+            value => value.to_string(),
+        }
+    }
+
+    /// Returns whether this value is or contains a function.
+    pub(crate) fn contains_function(&self) -> bool {
+        match self {
+            Value::Function(_) => true,
+            Value::List(list) => list.iter().any(Value::contains_function),
+            Value::Map(map) => map.values().any(Value::contains_function),
+            _ => false,
+        }
+    }
+
+    /// Compares two values structurally, as done by `==`.
+    ///
+    /// Integers and floats are equal if they represent the same number. Values of
+    /// other differing types are never equal.
+    pub(crate) fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Integer(lhs), Value::Float(rhs)) | (Value::Float(rhs), Value::Integer(lhs)) => {
+                compare_mixed(*lhs, *rhs) == Some(Ordering::Equal)
+            }
+            (Value::List(lhs), Value::List(rhs)) => {
+                lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(lhs, rhs)| lhs.equals(rhs))
+            }
+            (Value::Map(lhs), Value::Map(rhs)) => {
+                lhs.len() == rhs.len()
+                    && lhs
+                        .iter()
+                        .all(|(key, lhs)| rhs.get(key).is_some_and(|rhs| lhs.equals(rhs)))
+            }
+            (lhs, rhs) => lhs == rhs,
+        }
+    }
+
+    /// Orders two values, as done by `<`, `<=`, `>` and `>=`.
+    ///
+    /// Numbers are ordered numerically, strings by their characters and lists
+    /// lexicographically by their elements. Other values cannot be ordered.
+    pub(crate) fn compare(&self, other: &Value) -> Result<Ordering, RuntimeErrorKind> {
+        let ordering = match (self, other) {
+            (Value::Integer(lhs), Value::Integer(rhs)) => Some(lhs.cmp(rhs)),
+            (Value::Integer(lhs), Value::Float(rhs)) => compare_mixed(*lhs, *rhs),
+            (Value::Float(lhs), Value::Integer(rhs)) => {
+                compare_mixed(*rhs, *lhs).map(Ordering::reverse)
+            }
+            (Value::Float(lhs), Value::Float(rhs)) => lhs.partial_cmp(rhs),
+            (Value::String(lhs), Value::String(rhs)) => Some(lhs.cmp(rhs)),
+            (Value::List(lhs), Value::List(rhs)) => {
+                for (lhs, rhs) in lhs.iter().zip(rhs) {
+                    match lhs.compare(rhs)? {
+                        Ordering::Equal => continue,
+                        ordering => return Ok(ordering),
+                    }
+                }
+                Some(lhs.len().cmp(&rhs.len()))
+            }
+            _ => None,
+        };
+
+        ordering.ok_or(RuntimeErrorKind::NotComparable {
+            lhs: self.type_name(),
+            rhs: other.type_name(),
+        })
+    }
+
+    /// Orders two values totally, agreeing with [`Value::compare`] wherever it succeeds.
+    ///
+    /// Values that cannot be compared are ordered by type, with numbers first, then
+    /// strings, then lists, and `nan` after every other number.
+    pub(crate) fn total_cmp(&self, other: &Value) -> Ordering {
+        fn rank(value: &Value) -> u8 {
+            match value {
+                Value::Integer(_) => 0,
+                Value::Float(float) if !float.is_nan() => 0,
+                Value::Float(_) => 1,
+                Value::String(_) => 2,
+                Value::List(_) => 3,
+                _ => 4,
+            }
+        }
+
+        match (self, other) {
+            (Value::List(lhs), Value::List(rhs)) => lhs
+                .iter()
+                .zip(rhs)
+                .map(|(lhs, rhs)| lhs.total_cmp(rhs))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| lhs.len().cmp(&rhs.len())),
+            (lhs, rhs) => match lhs.compare(rhs) {
+                Ok(ordering) => ordering,
+                Err(_) => rank(lhs).cmp(&rank(rhs)),
+            },
+        }
+    }
+
     /// Returns `true` if this value is a string.
     pub fn is_string(&self) -> bool {
         matches!(self, Value::String(_))
@@ -309,6 +453,33 @@ impl Value {
     pub fn is_map(&self) -> bool {
         matches!(self, Value::Map(_))
     }
+
+    /// Returns `true` if this value is a function.
+    pub fn is_function(&self) -> bool {
+        matches!(self, Value::Function(_))
+    }
+}
+
+/// Orders an integer and a float exactly, which converting the integer to a float
+/// would not do above 2^53. Nothing is ordered with `nan`.
+fn compare_mixed(int: i64, float: f64) -> Option<Ordering> {
+    // 2^63, the first float above every integer
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+
+    if float.is_nan() {
+        None
+    } else if float >= LIMIT {
+        Some(Ordering::Less)
+    } else if float < -LIMIT {
+        Some(Ordering::Greater)
+    } else {
+        // The whole part is within the integer range, and the fraction breaks ties
+        let whole = float.trunc();
+        Some(
+            int.cmp(&(whole as i64))
+                .then_with(|| whole.partial_cmp(&float).unwrap_or(Ordering::Equal)),
+        )
+    }
 }
 
 impl std::fmt::Display for Value {
@@ -0,0 +1,134 @@
+mod common;
+
+use common::{eval, evaluated, formatted, located_error};
+use resl::{RuntimeErrorKind, Value, evaluate};
+
+#[test]
+fn test_postfix_bases() {
+    assert_eq!(evaluated("[1, 2, 3][0]"), "1");
+    assert_eq!(evaluated("[[1, 2], [3]][0][1:]"), "[2]");
+    assert_eq!(evaluated(r#"["a": ["b": 1]]["a"]["b"]"#), "1");
+    assert_eq!(evaluated(r#"{m = ["k": "v"]; m}["k"]"#), r#""v""#);
+    assert_eq!(
+        evaluated(r#"{servers = |env| [["host": env]]; servers("web")[0]["host"]}"#),
+        r#""web""#
+    );
+    assert_eq!(evaluated("{a = [1]; b = [2]; (a + b)[1]}"), "2");
+    assert_eq!(
+        evaluated("{a = [1]; b = [2]; (a + b) > (i, x): x * 10}"),
+        "[10, 20]"
+    );
+    assert_eq!(evaluated("[10, 20] > (i, x): x + i"), "[10, 21]");
+    assert_eq!(evaluated("{l = [[1, 2]]; l[0] > (i, x): -x}"), "[-1, -2]");
+    assert_eq!(evaluated("{l = [1]; -l[0]}"), "-1");
+
+    // The base is evaluated outside the scope of the loop variables
+    assert_eq!(
+        evaluated("{x = 5; {y = x; [y, y]} > (i, x): x + i}"),
+        "[5, 6]"
+    );
+
+    // Comparisons are still parsed as such
+    assert_eq!(evaluated("{x = 2; x > (1 + 1)}"), "false");
+
+    assert_eq!(
+        located_error("[1][5]"),
+        (
+            RuntimeErrorKind::IndexOutOfBounds { index: 5, len: 1 },
+            "5".to_string()
+        )
+    );
+    assert_eq!(
+        located_error("(1 + 2) > (i, x): x"),
+        (
+            RuntimeErrorKind::TypeMismatch {
+                expected: "list or map",
+                found: "integer"
+            },
+            "(1 + 2)".to_string()
+        )
+    );
+
+    // Formatting keeps the meaning of the base
+    for (input, expected) in [
+        ("(-x)[0]", "(-x)[0]"),
+        ("-x[0]", "-x[0]"),
+        ("(a + b)[0]", "(a+b)[0]"),
+        ("{a = 1; [a]} > (i, x): x", "{a=1;[a]}>(i,x):x"),
+    ] {
+        assert_eq!(formatted(input, false), expected);
+    }
+}
+
+#[test]
+fn test_ranges_and_flatten() {
+    assert_eq!(evaluated("0..3"), "[0, 1, 2]");
+    assert_eq!(evaluated("1..=3"), "[1, 2, 3]");
+    assert_eq!(evaluated("[3..1, 2..2, 2..=2]"), "[[], [], [2]]");
+    assert_eq!(evaluated("-2..1"), "[-2, -1, 0]");
+    assert_eq!(evaluated("{n = 2; 0..n + 1}"), "[0, 1, 2]");
+    assert_eq!(evaluated("[0.5, 1.0e2]"), "[0.5, 100.0]");
+
+    // Ranges and other operations can be iterated
+    assert_eq!(evaluated("0..3 > (i, n) : n * 10"), "[0, 10, 20]");
+    assert_eq!(evaluated("1..=4 > (i, n) ? n % 2 == 0 : n"), "[2, 4]");
+    assert_eq!(evaluated("[1] + [2] > (i, n) : -n"), "[-1, -2]");
+    assert_eq!(evaluated("[3 > 1, 3 > (1)]"), "[true, true]");
+
+    assert_eq!(
+        evaluated("flatten([[1, 2], [], 3, [[4]]])"),
+        "[1, 2, 3, [4]]"
+    );
+    assert_eq!(
+        evaluated("flatten(1..=3 > (i, n) : 0..n > (j, m) : [n, m])"),
+        "[[1, 0], [2, 0], [2, 1], [3, 0], [3, 1], [3, 2]]"
+    );
+
+    assert_eq!(
+        located_error("0..2.5"),
+        (
+            RuntimeErrorKind::TypeMismatch {
+                expected: "integer",
+                found: "float"
+            },
+            "2.5".to_string()
+        )
+    );
+    assert_eq!(
+        located_error("flatten(\"a\")"),
+        (
+            RuntimeErrorKind::TypeMismatch {
+                expected: "list",
+                found: "string"
+            },
+            "\"a\"".to_string()
+        )
+    );
+    assert_eq!(evaluate("\"a\"..1").unwrap(), Value::Null);
+    assert_eq!(
+        located_error("0..9223372036854775807"),
+        (
+            RuntimeErrorKind::RangeTooLarge(1_000_000),
+            "0..9223372036854775807".to_string()
+        )
+    );
+    assert_eq!(
+        located_error("-9223372036854775807..=9223372036854775807").0,
+        RuntimeErrorKind::RangeTooLarge(1_000_000)
+    );
+    assert!(matches!(
+        eval("1..=1000000"),
+        Value::List(list) if list.len() == 1_000_000
+    ));
+    assert_eq!(evaluate("0..1000001").unwrap(), Value::Null);
+
+    for (input, expected) in [
+        (
+            "{n=3;0..n+1>(i,x):x}",
+            "{\n    n = 3;\n    0..n + 1 > (i, x) : x\n}",
+        ),
+        ("1 ..= 2", "1..=2"),
+    ] {
+        assert_eq!(formatted(input, true), expected);
+    }
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use common::{eval, formatted};
+use resl::{Value, evaluate};
+
+#[test]
+fn test_comments() {
+    let input = r#"// Service configuration
+{
+    // Network
+    port = 8080; // http
+    hosts = [
+        "a", /* primary */
+        // fallback
+        "b"
+        // end of hosts
+    ];
+    limits = ["max": /* per node */ 100, "min": 1 // at least one
+    ];
+    port * 2 /* doubled */ / 2
+}
+// eof"#;
+
+    assert_eq!(eval(input), eval("{port = 8080; port}"));
+    assert_eq!(eval("10/2 // half"), Value::Integer(5));
+    assert_eq!(
+        eval(r#""http://example.com" /* url */"#),
+        Value::String("http://example.com".into())
+    );
+    assert!(evaluate("{a = 1; /* unterminated a}").is_err());
+
+    let pretty = formatted(input, true);
+    assert_eq!(
+        pretty,
+        r#"// Service configuration
+{
+    // Network
+    port = 8080; // http
+    hosts = [
+        "a", /* primary */
+        // fallback
+        "b"
+        // end of hosts
+    ];
+    limits = [
+        "max": /* per node */ 100,
+        "min": 1 // at least one
+    ];
+    port * 2 /* doubled */ / 2
+}
+// eof"#
+    );
+
+    // Formatting again keeps the comments in place
+    assert_eq!(formatted(&pretty, true), pretty);
+
+    // Line comments become block comments when not pretty-printing
+    assert_eq!(formatted("[1, // one\n 2]", false), "[1, /* one */ 2]");
+}
+
+#[test]
+fn test_comments_in_expressions() {
+    // Comments stay next to the node directly before or after them
+    assert_eq!(
+        formatted("{x = 1 /* c */ + 2; x}", false),
+        "{x=1 /* c */+2;x}"
+    );
+    assert_eq!(formatted("f(1, /* arg */ 2)", false), "f(1,/* arg */ 2)");
+    assert_eq!(formatted("f(1, 2 /* last */)", true), "f(1, 2 /* last */)");
+    assert_eq!(formatted("f(x /* n */ = 1)", true), "f(x /* n */ = 1)");
+    assert_eq!(formatted("x[/* i */ 0]", true), "x[/* i */ 0]");
+    assert_eq!(
+        formatted("? /* cond */ ok : /* yes */ 1 | /* no */ 2", false),
+        "?/* cond */ ok:/* yes */ 1|/* no */ 2"
+    );
+    assert_eq!(
+        formatted("|a /* first */, b = /* two */ 2| a + b", true),
+        "|a /* first */, b = /* two */ 2| a + b"
+    );
+    assert_eq!(
+        formatted("[1, 2] > (i /* index */, x): x", false),
+        "[1, 2]>(i /* index */,x):x"
+    );
+    assert_eq!(
+        formatted(r#"["a" /* k */: 1, /* b */ "b": /* v */ 2]"#, false),
+        r#"["a" /* k */: 1, /* b */ "b": /* v */ 2]"#
+    );
+    assert_eq!(
+        formatted(
+            "match x /* v */ { 1 /* one */ => 1, _ /* rest */ => 2 }",
+            false
+        ),
+        "match x /* v */{1 /* one */=>1,_ /* rest */=>2}"
+    );
+
+    // Bound names keep their comments, and comments after a binding its separator
+    assert_eq!(
+        formatted("{port /* p */ = 80 /* http */; port}", true),
+        "{\n    port /* p */ = 80 /* http */;\n    port\n}"
+    );
+    assert_eq!(
+        formatted("{port = 80; // the port\n port}", false),
+        "{port=80; /* the port */ port}"
+    );
+
+    // Line comments among other tokens become block comments
+    assert_eq!(
+        formatted("{x = 1 // one\n + 2; x}", true),
+        "{\n    x = 1 /* one */ + 2;\n    x\n}"
+    );
+    assert_eq!(
+        formatted("{port = 80 // the port\n; port}", false),
+        "{port=80 /* the port */;port}"
+    );
+
+    // Their text is kept as is, even if it would end a block comment
+    assert_eq!(formatted("[1 // a */ b\n, 2]", false), "[1 // a */ b\n, 2]");
+
+    // Or inside empty arguments and parameters
+    assert_eq!(formatted("f(/* none */)", false), "f(/* none */)");
+    assert_eq!(formatted("f(1, /* x */)", false), "f(1 /* x */)");
+    assert_eq!(formatted("|/* none */| 1", true), "|/* none */| 1");
+
+    // Formatting again keeps every comment in place
+    for input in [
+        "{x = 1 /* c */ + 2; x}",
+        "|a /* first */, b = /* two */ 2| a",
+        "[1 /* one */, /* two */ 2]",
+    ] {
+        for pretty in [false, true] {
+            let output = formatted(input, pretty);
+            assert_eq!(formatted(&output, pretty), output);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Helpers shared by the integration tests.
+
+// Each test crate uses only some of the helpers
+#![allow(dead_code)]
+
+use resl::{Engine, Error, RuntimeError, RuntimeErrorKind, Value, evaluate_strict, format};
+
+/// Evaluates `input` strictly, panicking if it fails.
+pub fn eval(input: &str) -> Value {
+    evaluate_strict(input).unwrap()
+}
+
+/// Evaluates `input` strictly and returns the value written as RESL, panicking
+/// if it fails.
+pub fn evaluated(input: &str) -> String {
+    eval(input).to_string()
+}
+
+/// Formats `input`, panicking if it cannot be parsed.
+pub fn formatted(input: &str, pretty: bool) -> String {
+    let mut output = String::new();
+    format(input, &mut output, pretty).unwrap();
+    output
+}
+
+/// Evaluates `input` strictly and returns the runtime error it fails with.
+pub fn runtime_error(input: &str) -> RuntimeError {
+    engine_error(&Engine::new().strict(true), input)
+}
+
+/// Evaluates `input` with `engine` and returns the runtime error it fails with.
+pub fn engine_error(engine: &Engine, input: &str) -> RuntimeError {
+    match engine.evaluate(input) {
+        Err(Error::Runtime(err)) => err,
+        other => panic!("Expected runtime error for {input:?}, got {other:?}"),
+    }
+}
+
+/// Evaluates `input` strictly and returns the source text of the expression it
+/// fails at.
+pub fn error_source(input: &str) -> String {
+    located_error(input).1
+}
+
+/// Evaluates `input` strictly and returns the kind of runtime error it fails with.
+pub fn error_kind(input: &str) -> RuntimeErrorKind {
+    runtime_error(input).kind
+}
+
+/// Evaluates `input` strictly and returns the kind of runtime error it fails with,
+/// along with the source text of the expression it fails at.
+pub fn located_error(input: &str) -> (RuntimeErrorKind, String) {
+    let err = runtime_error(input);
+    (
+        err.kind,
+        input[err.span.start()..err.span.end()].to_string(),
+    )
+}
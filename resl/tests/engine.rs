@@ -0,0 +1,170 @@
+mod common;
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use common::engine_error;
+use resl::{Engine, RuntimeErrorKind, Value, evaluate};
+
+#[test]
+fn test_native_functions() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+
+    let engine = Engine::new()
+        .register_fn("hostname", |args| match args {
+            [Value::String(prefix)] => Ok(Value::String(format!("{prefix}-01"))),
+            _ => Ok(Value::Null),
+        })
+        .register_fn("count", move |args| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(Value::Integer(args.len() as i64))
+        })
+        .register_fn("port_of", |args| match args {
+            [Value::String(service)] if service == "http" => Ok(Value::Integer(80)),
+            [Value::String(service)] => Err(RuntimeErrorKind::Custom(format!(
+                "unknown service {service}"
+            ))),
+            [other] => Err(RuntimeErrorKind::TypeMismatch {
+                expected: "string",
+                found: other.type_name(),
+            }),
+            _ => Err(RuntimeErrorKind::ArityMismatch {
+                expected: 1,
+                found: args.len(),
+            }),
+        })
+        // Registered functions take precedence over built-in ones
+        .register_fn("length", |_| Ok(Value::Integer(-1)));
+
+    assert_eq!(
+        engine
+            .evaluate(r#"{host = hostname("build"); ["host": host, "port": port_of("http")]}"#)
+            .unwrap(),
+        evaluate(r#"["host": "build-01", "port": 80]"#).unwrap()
+    );
+    assert_eq!(
+        engine.evaluate("[count(1, 2), count(null)]").unwrap(),
+        evaluate("[2, 1]").unwrap()
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(
+        engine.evaluate("length([1, 2])").unwrap(),
+        Value::Integer(-1)
+    );
+
+    // Failures are null in lenient mode and reported at the call in strict mode
+    assert_eq!(engine.evaluate("port_of(\"ftp\")").unwrap(), Value::Null);
+
+    let input = "{x = port_of(\"ftp\"); x}";
+    let err = engine_error(&engine.clone().strict(true), input);
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::Custom("unknown service ftp".into())
+    );
+    assert_eq!(&input[err.span.start()..err.span.end()], "port_of(\"ftp\")");
+}
+
+#[test]
+fn test_injected_variables() {
+    let input = r#"
+{
+    replicas = ? production : 3 | 1;
+    host = |service| concat(service, ".", region, ".example.com");
+    ["replicas": replicas, "api": host("api"), "zone": limits["zone"], "debug": debug]
+}
+"#;
+
+    let deployment = |production: bool, region: &str| {
+        Engine::new().define_vars([
+            ("production", Value::Boolean(production)),
+            ("region", Value::String(region.into())),
+            ("limits", evaluate(r#"["zone": "b"]"#).unwrap()),
+            ("debug", Value::Boolean(true)),
+        ])
+    };
+
+    assert_eq!(
+        deployment(true, "eu-west-1").evaluate(input).unwrap(),
+        evaluate(
+            r#"["replicas": 3, "api": "api.eu-west-1.example.com", "zone": "b", "debug": true]"#
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        deployment(false, "us-east-1").evaluate(input).unwrap(),
+        evaluate(
+            r#"["replicas": 1, "api": "api.us-east-1.example.com", "zone": "b", "debug": true]"#
+        )
+        .unwrap()
+    );
+
+    // Bindings declared by the document shadow injected variables
+    let engine = Engine::new().define_var("region", Value::String("eu-west-1".into()));
+    assert_eq!(
+        engine.evaluate("{region = \"local\"; region}").unwrap(),
+        Value::String("local".into())
+    );
+
+    // Later definitions replace earlier ones and built-in functions
+    let engine = Engine::new()
+        .register_fn("region", |_| Ok(Value::Null))
+        .define_var("region", Value::Integer(1))
+        .define_var("region", Value::Integer(2))
+        .define_var("length", Value::Integer(3));
+    assert_eq!(
+        engine.evaluate("[region, length]").unwrap(),
+        evaluate("[2, 3]").unwrap()
+    );
+
+    let err = engine_error(&engine.strict(true), "length([1])");
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::TypeMismatch {
+            expected: "function",
+            found: "integer"
+        }
+    );
+}
+
+#[test]
+fn test_env() {
+    // Cargo sets the package name for the test process
+    let engine = Engine::new()
+        .strict(true)
+        .allow_envs(["CARGO_PKG_NAME", "RESL_TEST_UNSET"]);
+
+    assert_eq!(
+        engine.evaluate(r#"env("CARGO_PKG_NAME")"#).unwrap(),
+        Value::String("resl".into())
+    );
+    assert_eq!(
+        engine.evaluate(r#"env("RESL_TEST_UNSET", 8080)"#).unwrap(),
+        Value::Integer(8080)
+    );
+
+    assert_eq!(
+        engine_error(&engine, r#"env("RESL_TEST_UNSET")"#).kind,
+        RuntimeErrorKind::EnvNotSet("RESL_TEST_UNSET".into())
+    );
+    assert_eq!(
+        engine_error(&engine, r#"env("PATH", "/bin")"#).kind,
+        RuntimeErrorKind::EnvNotAllowed("PATH".into())
+    );
+    assert_eq!(
+        engine_error(&engine, r#"env("CARGO_PKG_NAME", 1, 2)"#).kind,
+        RuntimeErrorKind::ArityMismatch {
+            expected: 2,
+            found: 3
+        }
+    );
+
+    // Access is disabled by default
+    assert_eq!(
+        engine_error(&Engine::new().strict(true), r#"env("CARGO_PKG_NAME")"#).kind,
+        RuntimeErrorKind::EnvNotAllowed("CARGO_PKG_NAME".into())
+    );
+    assert_eq!(evaluate(r#"env("CARGO_PKG_NAME")"#).unwrap(), Value::Null);
+}
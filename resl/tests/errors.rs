@@ -0,0 +1,57 @@
+mod common;
+
+use common::{error_source, runtime_error};
+use resl::{RuntimeErrorKind, Value, evaluate};
+
+#[test]
+fn test_strict_errors() {
+    let err = runtime_error("{a = 1; b = \"x\"; a + b}");
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::TypeMismatch {
+            expected: "integer or float",
+            found: "string"
+        }
+    );
+    assert_eq!((err.span.start(), err.span.end()), (21, 22));
+
+    let err = runtime_error("{f = |a, b| a + b; f(1)}");
+    assert_eq!(err.kind, RuntimeErrorKind::MissingArgument("b".into()));
+
+    let err = runtime_error("{f = |a| a; f(1, 2)}");
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::ArityMismatch {
+            expected: 1,
+            found: 2
+        }
+    );
+
+    let err = runtime_error("{\n    x = 1;\n    y\n}");
+    assert_eq!(err.kind, RuntimeErrorKind::UnknownIdentifier("y".into()));
+    assert_eq!((err.line_number, err.column), (3, 5));
+
+    let err = runtime_error("{x = y; y = x; x}");
+    assert_eq!(err.kind, RuntimeErrorKind::CyclicReference("x".into()));
+
+    let err = runtime_error("{l = [1, 2]; l[5]}");
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::IndexOutOfBounds { index: 5, len: 2 }
+    );
+
+    // Lenient evaluation keeps producing null for the same inputs
+    assert_eq!(evaluate("{l = [1, 2]; l[5]}").unwrap(), Value::Null);
+    assert_eq!(evaluate("{x = y; y = x; x}").unwrap(), Value::Null);
+}
+
+#[test]
+fn test_error_spans() {
+    assert_eq!(error_source("{l = [[1], 2]; l[1][0]}"), "l[1]");
+    assert_eq!(error_source("{l = [1, 2]; l[0:\"a\"]}"), "\"a\"");
+    assert_eq!(error_source("{x = 1; x(2)}"), "x");
+    assert_eq!(error_source("{f = |a| a; f(1, 2)}"), "f(1, 2)");
+    assert_eq!(error_source("{n = 4; n > (i, x): x}"), "n");
+    assert_eq!(error_source("? (1 + 2) : 1 | 2"), "(1 + 2)");
+    assert_eq!(error_source("[\"a\": !5]"), "5");
+}
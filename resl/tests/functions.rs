@@ -0,0 +1,359 @@
+mod common;
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use common::{engine_error, error_kind, evaluated, formatted, located_error};
+use resl::{
+    Engine, MemoryResolver, RuntimeErrorKind, Value, evaluate, evaluate_strict, from_value, parse,
+};
+
+#[test]
+fn test_environments() {
+    // Bindings of a block are computed again for every item
+    assert_eq!(
+        evaluated(
+            r#"{servers = [["host": "a"], ["host": "b"]]; servers > (i, s): {h = s["host"]; h}}"#
+        ),
+        r#"["a", "b"]"#
+    );
+    assert_eq!(
+        evaluated("[[0, 1], [10]] > (i, l): l > (j, x): x + i"),
+        "[[0, 1], [11]]"
+    );
+
+    // Recursion
+    assert_eq!(
+        evaluated("{fact = |n| ? (n <= 1) : 1 | n * fact(n - 1); fact(10)}"),
+        "3628800"
+    );
+    assert_eq!(
+        evaluated("{fib = |n| ? (n < 2) : n | fib(n - 1) + fib(n - 2); fib(15)}"),
+        "610"
+    );
+
+    // Arguments are evaluated in the caller's scope, bodies in the declaration's scope
+    assert_eq!(
+        evaluated("{x = 1; f = |x| x + 1; g = |y| f(x); g(10)}"),
+        "2"
+    );
+    assert_eq!(
+        evaluated("{x = 1; f = |y| {z = y * 2; z + x}; [f(1), f(f(2))]}"),
+        "[3, 11]"
+    );
+
+    let input = "{f = |n| f(n + 1); f(0)}";
+    assert_eq!(error_kind(input), RuntimeErrorKind::CallDepthExceeded(128));
+    assert_eq!(evaluate(input).unwrap(), Value::Null);
+
+    // Cycles are still reported
+    assert!(evaluate_strict("{a = b; b = a; a}").is_err());
+}
+
+#[test]
+fn test_cached_closures() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+
+    let engine = Engine::new()
+        .register_fn("track", move |args| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(args[0].clone())
+        })
+        .strict(true);
+    let eval = |input: &str| engine.evaluate(input).unwrap().to_string();
+
+    // Bindings holding closures are computed once, like any other binding
+    assert_eq!(
+        eval("{f = track(|x| x + 1); [f(1), f(2), f(3)]}"),
+        "[2, 3, 4]"
+    );
+    assert_eq!(calls.swap(0, Ordering::SeqCst), 1);
+
+    assert_eq!(
+        eval("{fs = track([|x| x, |x| -x]); g = fs[1]; h = fs[0]; [g(1), h(2), length(fs)]}"),
+        "[-1, 2, 2]"
+    );
+    assert_eq!(calls.swap(0, Ordering::SeqCst), 1);
+
+    // Including inside functions and recursive closures capturing their own block
+    assert_eq!(
+        eval("{make = |n| {step = track(|x| x + n); [step(0), step(1)]}; [make(1), make(2)]}"),
+        "[[1, 2], [2, 3]]"
+    );
+    assert_eq!(calls.swap(0, Ordering::SeqCst), 2);
+
+    assert_eq!(
+        eval("{fact = track(|n| ? n <= 1 : 1 | n * fact(n - 1)); fact(5)}"),
+        "120"
+    );
+    assert_eq!(calls.swap(0, Ordering::SeqCst), 1);
+
+    // Closures returned from an evaluation still compute their bindings
+    let f = engine
+        .evaluate("{base = track(|x| x * 10); |x| base(x) + 1}")
+        .unwrap();
+    let engine = engine.define_var("f", f);
+    assert_eq!(
+        engine.evaluate("[f(1), f(2)]").unwrap().to_string(),
+        "[11, 21]"
+    );
+}
+
+#[test]
+fn test_closures() {
+    // Functions are values that capture their environment
+    assert_eq!(
+        evaluated("{add = |n| |x| x + n; add5 = add(5); add5(10)}"),
+        "15"
+    );
+    assert_eq!(evaluated("{c = {n = 3; |x| x * n}; c(2)}"), "6");
+    assert_eq!(
+        evaluated("{fs = [|x| x + 1, |x| x * 10]; f = fs[1]; f(3)}"),
+        "30"
+    );
+    assert_eq!(
+        evaluated("{compose = |f, g| |x| f(g(x)); h = compose(|x| x + 1, |x| x * 2); h(5)}"),
+        "11"
+    );
+    assert_eq!(
+        evaluated("{f = |x| x; [f, type_of(f), type_of(length)]}"),
+        r#"[<function>, "function", "function"]"#
+    );
+    assert_eq!(evaluated("{f = |x| x; (f == f)}"), "false");
+
+    // Higher-order built-ins
+    assert_eq!(evaluated("map([1, 2, 3], |x| x * 2)"), "[2, 4, 6]");
+    assert_eq!(evaluated("map([[1], [], [2, 3]], length)"), "[1, 0, 2]");
+    assert_eq!(evaluated("filter([1, 2, 3, 4], |x| x % 2 == 0)"), "[2, 4]");
+    assert_eq!(evaluated("reduce([1, 2, 3, 4], 0, |acc, x| acc + x)"), "10");
+    assert_eq!(evaluated("reduce([], 7, |acc, x| acc + x)"), "7");
+    assert_eq!(
+        evaluated(r#"sort_by(["ccc", "a", "bb", "d"], length)"#),
+        r#"["a", "d", "bb", "ccc"]"#
+    );
+    assert_eq!(
+        evaluated(
+            "[any([1, 2], |x| x > 1), all([1, 2], |x| x > 1), any([], |x| true), all([], |x| \
+             false)]"
+        ),
+        "[true, false, false, true]"
+    );
+
+    assert_eq!(
+        located_error("filter([1], |x| x)"),
+        (
+            RuntimeErrorKind::TypeMismatch {
+                expected: "boolean",
+                found: "integer"
+            },
+            "|x| x".to_string()
+        )
+    );
+    assert_eq!(
+        located_error("map([1], 2)"),
+        (
+            RuntimeErrorKind::TypeMismatch {
+                expected: "function",
+                found: "integer"
+            },
+            "2".to_string()
+        )
+    );
+    assert_eq!(
+        located_error("map([1], |x, y| x)"),
+        (
+            RuntimeErrorKind::MissingArgument("y".to_string()),
+            "|x, y| x".to_string()
+        )
+    );
+    assert_eq!(
+        located_error(r#"sort_by([1, "a"], |x| x)"#),
+        (
+            RuntimeErrorKind::NotComparable {
+                lhs: "string",
+                rhs: "integer"
+            },
+            "|x| x".to_string()
+        )
+    );
+    assert_eq!(evaluate("filter([1], |x| x)").unwrap(), Value::List(vec![]));
+
+    // Functions keep the contexts of the module declaring them
+    let engine = Engine::new()
+        .resolver(
+            MemoryResolver::new()
+                .with_module("lib.resl", r#"{base = 10; ["offset": |x| x + base]}"#),
+        )
+        .register_fn("twice", |args| match args {
+            [Value::Integer(i)] => Ok(Value::Integer(i * 2)),
+            _ => Err(RuntimeErrorKind::Custom("expected an integer".to_string())),
+        })
+        .strict(true);
+    assert_eq!(
+        engine
+            .evaluate(r#"{lib = import("lib.resl"); offset = lib["offset"]; map([1, 2], offset)}"#)
+            .unwrap()
+            .to_string(),
+        "[11, 12]"
+    );
+    assert_eq!(
+        engine.evaluate("map([1, 2], twice)").unwrap().to_string(),
+        "[2, 4]"
+    );
+
+    // Errors in their bodies are located in the source of that module
+    let engine = Engine::new()
+        .resolver(MemoryResolver::new().with_module(
+            "lib.resl",
+            "\n\n// Adds a missing value\n{f = |x| x + missing_thing_here; f}",
+        ))
+        .strict(true);
+    let module_err = engine_error(&engine, r#"{f = import("lib.resl"); f(1)}"#);
+    assert_eq!(
+        module_err.kind,
+        RuntimeErrorKind::UnknownIdentifier("missing_thing_here".to_string())
+    );
+    assert_eq!((module_err.line_number, module_err.column), (4, 14));
+    assert_eq!(
+        module_err.line_content,
+        "{f = |x| x + missing_thing_here; f}"
+    );
+
+    // Keys of different types are not comparable, however many there are
+    let keys = "sort_by(0..40 > (i, x): ? x % 3 == 0 : \"s\" | 40 - x, |x| x)";
+    assert!(matches!(
+        located_error(keys).0,
+        RuntimeErrorKind::NotComparable { .. }
+    ));
+    assert!(matches!(
+        located_error("sort_by([[1, 2], [1, \"a\"], [0, 2.0], 3], |x| x)").0,
+        RuntimeErrorKind::NotComparable { .. }
+    ));
+    assert!(matches!(
+        located_error("sort_by([1.0, nan, 2], |x| x)").0,
+        RuntimeErrorKind::NotComparable { .. }
+    ));
+    assert_eq!(evaluate(keys).unwrap(), Value::Null);
+    assert_eq!(
+        evaluated("sort_by([[2, 1], [1, 3.5], [1, 3]], |x| x)"),
+        "[[1, 3], [1, 3.5], [2, 1]]"
+    );
+
+    // Functions have no data representation
+    let value = evaluate("|x| x").unwrap();
+    assert!(value.is_function());
+    assert!(from_value::<i64>(value).is_err());
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Value>();
+    assert_send_sync::<Engine>();
+}
+
+#[test]
+fn test_parameters() {
+    assert_eq!(evaluated(r#"{now = || "today"; now()}"#), r#""today""#);
+    assert_eq!(
+        evaluated("{f = |...xs| length(xs); [f(), f(1, 2)]}"),
+        "[0, 2]"
+    );
+    assert_eq!(
+        evaluated(r#"{f = |host, port = 443| "${host}:${port}"; [f("a"), f("a", 80)]}"#),
+        r#"["a:443", "a:80"]"#
+    );
+    assert_eq!(
+        evaluated("{f = |a, b = 2, c = a + b| [a, b, c]; [f(1), f(1, c = 0), f(b = 5, a = 1)]}"),
+        "[[1, 2, 3], [1, 2, 0], [1, 5, 6]]"
+    );
+    assert_eq!(
+        evaluated("{f = |a, ...rest| [a, rest]; [f(1), f(1, 2, 3)]}"),
+        "[[1, []], [1, [2, 3]]]"
+    );
+    assert_eq!(evaluated("{f = |a = 1, b| [a, b]; f(b = 2)}"), "[1, 2]");
+    assert_eq!(evaluated("{f = |a, b,| a + b; f(1, 2,)}"), "3");
+
+    // Defaults are evaluated for every call
+    assert_eq!(
+        evaluated("{f = |x, l = [x]| push(l, 0); [f(1), f(2)]}"),
+        "[[1, 0], [2, 0]]"
+    );
+
+    assert_eq!(
+        located_error("{f = |a, b = 1| a; f()}"),
+        (
+            RuntimeErrorKind::MissingArgument("a".to_string()),
+            "f()".to_string()
+        )
+    );
+    assert_eq!(
+        located_error("{f = |a, b = 1| a; f(1, 2, 3)}"),
+        (
+            RuntimeErrorKind::ArityMismatch {
+                expected: 2,
+                found: 3
+            },
+            "f(1, 2, 3)".to_string()
+        )
+    );
+    assert_eq!(
+        located_error("{f = |a, b| a; f(b = 1)}"),
+        (
+            RuntimeErrorKind::MissingArgument("a".to_string()),
+            "f(b = 1)".to_string()
+        )
+    );
+    assert_eq!(
+        located_error("{f = |a| a; f(c = 1)}"),
+        (
+            RuntimeErrorKind::UnknownArgument("c".to_string()),
+            "c".to_string()
+        )
+    );
+    assert_eq!(
+        located_error("{f = |a| a; f(1, a = 2)}"),
+        (
+            RuntimeErrorKind::DuplicateArgument("a".to_string()),
+            "a".to_string()
+        )
+    );
+    assert_eq!(
+        located_error("length(value = [])"),
+        (
+            RuntimeErrorKind::UnknownArgument("value".to_string()),
+            "value".to_string()
+        )
+    );
+
+    // Positional arguments cannot follow named ones, and the rest parameter comes last
+    for input in ["f(a = 1, 2)", "|...rest, a| a", "|a b| a", "f(,)", "|,| 1"] {
+        assert!(evaluate(input).is_err(), "{input} should not parse");
+    }
+
+    // Parameters have distinct names
+    for input in [
+        "|a, a| a",
+        "|a, b = 1, ...a| a",
+        "{f = |x, y, x = 2| x; f(1, 2)}",
+    ] {
+        let err = parse(input).unwrap_err();
+        assert!(
+            err.expected
+                .iter()
+                .any(|e| e == "a distinct parameter name"),
+            "Expected a duplicate parameter error for {input:?}, got {err:?}"
+        );
+    }
+    assert_eq!(parse("|a, /* again */ a| a").unwrap_err().column, 17);
+
+    for (input, expected) in [
+        ("{f=||1;f()}", "{f=||1;f()}"),
+        (
+            "{f = |a,b = 2,...c| a; f(1, b=3)}",
+            "{f=|a,b=2,...c|a;f(1,b=3)}",
+        ),
+    ] {
+        assert_eq!(formatted(input, false), expected);
+    }
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use common::engine_error;
+use resl::{Engine, FileResolver, MemoryResolver, RuntimeErrorKind, Value, evaluate};
+
+#[test]
+fn test_imports() {
+    let loads = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&loads);
+
+    let resolver = MemoryResolver::new()
+        .with_module(
+            "common.resl",
+            r#"{n = track(1); ["region": "eu-west-1", "n": n]}"#,
+        )
+        .with_module(
+            "services/api.resl",
+            r#"{common = import("../common.resl"); ["region": common["region"], "port": 80]}"#,
+        )
+        .with_module(
+            "services/web.resl",
+            r#"{common = import("./../common.resl"); ["region": common["region"], "port": 443]}"#,
+        )
+        .with_module("cycle/a.resl", r#"import("b.resl")"#)
+        .with_module("cycle/b.resl", r#"import("a.resl")"#)
+        .with_module("broken.resl", "[1, ");
+
+    let engine = Engine::new()
+        .resolver(resolver)
+        .register_fn("track", move |args| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(args[0].clone())
+        });
+
+    assert_eq!(
+        engine
+            .evaluate(r#"[import("services/api.resl"), import("services/web.resl")]"#)
+            .unwrap(),
+        evaluate(r#"[["region": "eu-west-1", "port": 80], ["region": "eu-west-1", "port": 443]]"#)
+            .unwrap()
+    );
+    // Modules imported more than once are evaluated once
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+    // Failures are null in lenient mode
+    assert_eq!(
+        engine.evaluate(r#"import("cycle/a.resl")"#).unwrap(),
+        Value::Null
+    );
+    assert_eq!(
+        engine.evaluate(r#"import("missing.resl")"#).unwrap(),
+        Value::Null
+    );
+
+    let engine = engine.strict(true);
+    let import_error = |input: &str| engine_error(&engine, input);
+
+    let err = import_error(r#"{x = import("cycle/a.resl"); x}"#);
+    assert_eq!((err.span.start(), err.span.end()), (5, 27));
+    let RuntimeErrorKind::ImportFailed { path, reason } = err.kind else {
+        panic!("Expected import failure, got {:?}", err.kind);
+    };
+    assert_eq!(path, "cycle/a.resl");
+    assert!(reason.contains("`a.resl` is already being imported"));
+
+    let err = import_error(r#"import("missing.resl")"#);
+    assert!(matches!(
+        err.kind,
+        RuntimeErrorKind::ImportFailed { path, .. } if path == "missing.resl"
+    ));
+
+    let err = import_error(r#"import("broken.resl")"#);
+    let RuntimeErrorKind::ImportFailed { reason, .. } = err.kind else {
+        panic!("Expected import failure, got {:?}", err.kind);
+    };
+    // The source of the module is not revealed
+    assert_eq!(reason, "invalid syntax at line 1, column 5");
+
+    let err = import_error("import(1)");
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::TypeMismatch {
+            expected: "string",
+            found: "integer"
+        }
+    );
+}
+
+#[test]
+fn test_file_imports() {
+    let dir = std::env::temp_dir().join(format!("resl-imports-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("services")).unwrap();
+    std::fs::write(dir.join("common.resl"), r#"["region": "eu-west-1"]"#).unwrap();
+    std::fs::write(
+        dir.join("services/api.resl"),
+        r#"{common = import("../common.resl"); common["region"]}"#,
+    )
+    .unwrap();
+
+    let secret = format!("resl-secret-{}", std::process::id());
+    std::fs::write(
+        dir.join("services/escape.resl"),
+        format!("import({:?})", format!("../../{secret}")),
+    )
+    .unwrap();
+    std::fs::write(dir.join("..").join(&secret), "top secret").unwrap();
+
+    let engine = Engine::new().strict(true).resolver(FileResolver::new(&dir));
+    let result = engine.evaluate(r#"import("services/api.resl")"#);
+
+    let import_reason = |engine: &Engine, input: &str| match engine_error(engine, input).kind {
+        RuntimeErrorKind::ImportFailed { reason, .. } => reason,
+        kind => panic!("Expected import failure for {input:?}, got {kind:?}"),
+    };
+
+    // Files outside the base directory cannot be imported
+    let secret = dir.join("..").join(secret).canonicalize().unwrap();
+    let absolute = format!("import({:?})", secret.to_str().unwrap());
+    let relative = format!(
+        "import({:?})",
+        format!("../{}", secret.file_name().unwrap().to_str().unwrap())
+    );
+    let escapes = [
+        import_reason(&engine, &relative),
+        import_reason(&engine, &absolute),
+        import_reason(&engine, r#"import("services/escape.resl")"#),
+    ];
+
+    // Imports are refused unless the host sets a resolver
+    let denied = import_reason(&Engine::new().strict(true), r#"import("common.resl")"#);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    std::fs::remove_file(&secret).unwrap();
+
+    assert_eq!(result.unwrap(), Value::String("eu-west-1".into()));
+    for reason in &escapes {
+        assert!(reason.contains("outside the base directory"), "{reason}");
+        assert!(!reason.contains("top secret"));
+    }
+    assert_eq!(denied, "imports are disabled");
+    assert_eq!(evaluate(r#"import("/etc/passwd")"#).unwrap(), Value::Null);
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::{error_kind, eval, formatted};
+use resl::{RuntimeErrorKind, Value, evaluate};
+
+#[test]
+fn test_quoted_keys() {
+    let value = eval(
+        r#"["app.kubernetes.io/name": "web", "Content-Type: json": 1, "a \"b\" \\ c": 2, "\${x}": 3]"#,
+    );
+
+    let Value::Map(map) = &value else {
+        panic!("Expected a map, got {value:?}");
+    };
+    let mut keys = map.keys().map(String::as_str).collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(
+        keys,
+        [
+            "${x}",
+            "Content-Type: json",
+            "a \"b\" \\ c",
+            "app.kubernetes.io/name"
+        ]
+    );
+
+    // Formatted values evaluate back to themselves
+    assert_eq!(eval(&value.to_string()), value);
+
+    assert_eq!(
+        formatted(r#"["with space":"a b"]"#, true),
+        "[\n    \"with space\": \"a b\"\n]"
+    );
+
+    // Strings that are not followed by `:` are still list items
+    assert_eq!(
+        eval(r#"["a", "b${1}"]"#),
+        Value::List(vec![
+            Value::String("a".to_string()),
+            Value::String("b1".to_string())
+        ])
+    );
+}
+
+#[test]
+fn test_computed_keys() {
+    assert_eq!(
+        eval(r#"{prefix = "db"; [(prefix + "_host"): "localhost", "port": 5432]}"#),
+        eval(r#"["db_host": "localhost", "port": 5432]"#)
+    );
+    assert_eq!(eval(r#"["a": 1, ("a"): 2]"#), eval(r#"["a": 2]"#));
+    // Parenthesized expressions without `:` are still list items
+    assert_eq!(
+        eval("[(1 + 2) * 3, (4)]"),
+        Value::List(vec![Value::Integer(9), Value::Integer(4)])
+    );
+
+    assert_eq!(
+        eval(
+            r#"{c = ["host": "db.local", "password": null, "port": 5432];
+                    c > (k, v) ? v != null : "DB_" + k : to_str(v)}"#
+        ),
+        eval(r#"["DB_host": "db.local", "DB_port": "5432"]"#)
+    );
+    assert_eq!(
+        eval(r#"{c = ["a": 1, "b": 2, "c": 3]; c > (k, v) ? v >= 2 : v * 10}"#),
+        eval(r#"["b": 20, "c": 30]"#)
+    );
+    assert_eq!(
+        eval("{l = [1, 2, 3, 4]; l > (i, x) ? x % 2 == 0 : x}"),
+        eval("[2, 4]")
+    );
+    assert_eq!(
+        eval(r#"{l = ["http", "https"]; l > (i, name): name: 8080 + i}"#),
+        eval(r#"["http": 8080, "https": 8081]"#)
+    );
+
+    assert_eq!(
+        error_kind("[(1): 2]"),
+        RuntimeErrorKind::TypeMismatch {
+            expected: "string",
+            found: "integer"
+        }
+    );
+    // Entries with invalid keys are left out in lenient mode
+    assert_eq!(
+        evaluate(r#"{l = [1, 2]; l > (i, x): ? x == 1 : "one" | x: i}"#).unwrap(),
+        eval(r#"["one": 0]"#)
+    );
+
+    assert_eq!(
+        formatted(r#"{p="a";m=[(p+"b"):1];m>(k,v)?v>0:k:v}"#, true),
+        "{\n    p = \"a\";\n    m = [\n        (p + \"b\"): 1\n    ];\n    m > (k, v) ? v > 0 : \
+         k: v\n}"
+    );
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{eval, formatted};
+use resl::{Value, parse};
+
+#[test]
+fn test_numbers() {
+    assert_eq!(eval("0xff"), Value::Integer(255));
+    assert_eq!(eval("0o644"), Value::Integer(0o644));
+    assert_eq!(eval("0b1010_0101"), Value::Integer(0b1010_0101));
+    assert_eq!(eval("-0x10"), Value::Integer(-16));
+    assert_eq!(eval("1_000_000"), Value::Integer(1_000_000));
+    assert_eq!(eval("-9223372036854775808"), Value::Integer(i64::MIN));
+    assert_eq!(eval("1e-3"), Value::Float(0.001));
+    assert_eq!(eval("2.5E+2"), Value::Float(250.0));
+    assert_eq!(eval("1_000.000_1"), Value::Float(1000.0001));
+    assert_eq!(eval("-inf"), Value::Float(f64::NEG_INFINITY));
+    assert!(matches!(eval("nan"), Value::Float(f) if f.is_nan()));
+    assert_eq!(eval("{info = 1; info}"), Value::Integer(1));
+    // `inf` and `nan` can still be bound like any other name
+    assert_eq!(eval("{inf = 1; inf}"), Value::Integer(1));
+    assert_eq!(eval("{nan = 0; -nan}"), Value::Integer(0));
+    assert_eq!(eval("{f = |inf| inf; f(2)}"), Value::Integer(2));
+
+    for (input, expected) in [
+        ("9223372036854775808", "integer within the 64-bit range"),
+        ("0x8000_0000_0000_0000", "integer within the 64-bit range"),
+        ("0x", "hexadecimal digit"),
+        ("0b102", "end of input"),
+        ("1e", "exponent"),
+        ("1__0", "end of input"),
+    ] {
+        let err = parse(input).unwrap_err();
+        assert!(
+            err.expected.iter().any(|e| e == expected),
+            "Expected {expected:?} for {input:?}, got {err:?}"
+        );
+    }
+    assert_eq!(parse("[1, 99999999999999999999]").unwrap_err().column, 5);
+
+    // Formatted floats evaluate back to floats
+    let value = eval("[1.0, 0.5, 1e300, 1e-7, inf, -inf]");
+    assert_eq!(value.to_string(), "[1.0, 0.5, 1e300, 1e-7, inf, -inf]");
+    assert_eq!(eval(&value.to_string()), value);
+
+    // Formatting keeps numbers as written
+    let input = "[0x1F, 0o644, 0b1_0, 1_000, 1e3, -0x10, nan, -inf]";
+    assert_eq!(formatted(input, false), input);
+
+    assert_eq!(
+        formatted("match 0o7 {0o7 => 1_0, 1.5e2 => 2}", true),
+        "match 0o7 {\n    0o7 => 1_0,\n    1.5e2 => 2\n}"
+    );
+}
@@ -0,0 +1,255 @@
+mod common;
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use common::{engine_error, error_kind, eval, formatted};
+use resl::{Engine, RuntimeErrorKind, Value, evaluate};
+
+#[test]
+fn test_precedence() {
+    assert_eq!(eval("10 - 3 - 2"), Value::Integer(5));
+    assert_eq!(eval("100 / 10 / 5"), Value::Integer(2));
+    assert_eq!(eval("2 + 3 * 4"), Value::Integer(14));
+    assert_eq!(eval("(2 + 3) * 4"), Value::Integer(20));
+    assert_eq!(eval("10 - (3 - 2)"), Value::Integer(9));
+    assert_eq!(eval("-2 * 3 + 1"), Value::Integer(-5));
+    assert_eq!(eval("-(2 + 3) * 2"), Value::Integer(-10));
+    assert_eq!(eval("!false && false"), Value::Boolean(false));
+    assert_eq!(eval("1 + 2 * 3 == 7 && 2 > 1"), Value::Boolean(true));
+    assert_eq!(eval("true || false && false"), Value::Boolean(true));
+    assert_eq!(eval("{x = 5; x > 3 && x <= 10}"), Value::Boolean(true));
+    assert_eq!(eval("{x = 5; x > (2 + 2)}"), Value::Boolean(true));
+    assert_eq!(eval("{l = [1, 2]; l > (i, v): v * 2 + i}"), eval("[2, 5]"));
+
+    // Explicit parentheses are kept, implicit grouping is not added
+    assert_eq!(
+        formatted("(1 + 2) * 3 - 4 / (5 - 6) - ((7))", true),
+        "(1 + 2) * 3 - 4 / (5 - 6) - 7"
+    );
+}
+
+#[test]
+fn test_operators() {
+    assert_eq!(eval("17 % 5"), Value::Integer(2));
+    assert_eq!(eval("-7 % 3"), Value::Integer(-1));
+    assert_eq!(eval("7.5 % 2"), Value::Float(1.5));
+    assert_eq!(eval("-7 / 2"), Value::Integer(-3));
+    assert_eq!(eval("-7 ~/ 2"), Value::Integer(-4));
+    assert_eq!(eval("7 ~/ -2"), Value::Integer(-4));
+    assert_eq!(eval("-8 ~/ 2"), Value::Integer(-4));
+    assert_eq!(eval("7.5 ~/ 2"), Value::Integer(3));
+    assert_eq!(eval("-7.5 ~/ 2"), Value::Integer(-4));
+    assert_eq!(eval("2 ** 10"), Value::Integer(1024));
+    assert_eq!(eval("2 ** 3 ** 2"), Value::Integer(512));
+    assert_eq!(eval("2 ** -1"), Value::Float(0.5));
+    assert_eq!(eval("-2 ** 2"), Value::Integer(-4));
+    assert_eq!(eval("{x = 3; -x ** 2}"), Value::Integer(-9));
+    assert_eq!(eval("(-2) ** 2"), Value::Integer(4));
+    assert_eq!(eval("2 ** -2 ** 2"), Value::Float(0.0625));
+    assert_eq!(eval("12 & 10"), Value::Integer(8));
+    assert_eq!(eval("12 | 10"), Value::Integer(14));
+    assert_eq!(eval("12 ^ 10"), Value::Integer(6));
+    assert_eq!(eval("1 << 4 + 1"), Value::Integer(32));
+    assert_eq!(eval("-16 >> 2"), Value::Integer(-4));
+    assert_eq!(eval("1 | 2 == 3"), Value::Boolean(true));
+
+    // `|` separates the branches of a conditional unless bracketed
+    assert_eq!(eval("? true : 1 | 2"), Value::Integer(1));
+    assert_eq!(eval("? false : 1 | 2 | 4"), Value::Integer(6));
+    assert_eq!(eval("? true : (1 | 2) | 0"), Value::Integer(3));
+    assert_eq!(eval("? true : [1 | 2] | []"), eval("[3]"));
+
+    assert_eq!(error_kind("1 ~/ 0"), RuntimeErrorKind::DivisionByZero);
+    assert_eq!(error_kind("1 % 0"), RuntimeErrorKind::DivisionByZero);
+    assert_eq!(error_kind("0 ** -1"), RuntimeErrorKind::DivisionByZero);
+    assert_eq!(error_kind("0.0 ** -0.5"), RuntimeErrorKind::DivisionByZero);
+    assert_eq!(error_kind("1.5 % 0.0"), RuntimeErrorKind::DivisionByZero);
+    assert_eq!(
+        error_kind("(-9223372036854775807 - 1) ~/ -1"),
+        RuntimeErrorKind::Overflow
+    );
+    assert_eq!(error_kind("2 ** 63"), RuntimeErrorKind::Overflow);
+    assert_eq!(error_kind("1 << 64"), RuntimeErrorKind::Overflow);
+    assert_eq!(error_kind("1 >> -1"), RuntimeErrorKind::Overflow);
+    assert!(matches!(
+        error_kind("1 & 1.0"),
+        RuntimeErrorKind::TypeMismatch { .. }
+    ));
+
+    // Lenient evaluation turns failures into null
+    assert_eq!(
+        evaluate("[1 ~/ 0, 2 ** 64]").unwrap().to_string(),
+        "[null, null]"
+    );
+
+    assert_eq!(
+        formatted("2**-1+7~/2%3|1<<2&3^4", true),
+        "2 ** -1 + 7 ~/ 2 % 3 | 1 << 2 & 3 ^ 4"
+    );
+
+    // A negated base keeps its parentheses
+    assert_eq!(
+        formatted("[(-2)**2, -2**2, -(2**2)]", false),
+        "[(-2)**2, -2**2, -(2**2)]"
+    );
+}
+
+#[test]
+fn test_checked_arithmetic() {
+    // Mixed operands are promoted to floats
+    assert_eq!(eval("1 + 0.6"), Value::Float(1.6));
+    assert_eq!(eval("0.6 + 1"), Value::Float(1.6));
+    assert_eq!(eval("3 / 2.0"), Value::Float(1.5));
+    assert_eq!(eval("7 / 2"), Value::Integer(3));
+    assert_eq!(eval("-7 / 2"), Value::Integer(-3));
+
+    assert_eq!(
+        error_kind("9223372036854775807 + 1"),
+        RuntimeErrorKind::Overflow
+    );
+    assert_eq!(
+        error_kind("-9223372036854775807 - 2"),
+        RuntimeErrorKind::Overflow
+    );
+    assert_eq!(
+        error_kind("4294967296 * 4294967296"),
+        RuntimeErrorKind::Overflow
+    );
+    assert_eq!(
+        error_kind("(-9223372036854775807 - 1) / -1"),
+        RuntimeErrorKind::Overflow
+    );
+    assert_eq!(
+        error_kind("{x = -9223372036854775807 - 1; -x}"),
+        RuntimeErrorKind::Overflow
+    );
+    assert_eq!(error_kind("1 / 0"), RuntimeErrorKind::DivisionByZero);
+    assert_eq!(error_kind("1 / 0.0"), RuntimeErrorKind::DivisionByZero);
+
+    assert_eq!(
+        evaluate("[1 / 0, 9223372036854775807 + 1]")
+            .unwrap()
+            .to_string(),
+        "[null, null]"
+    );
+}
+
+#[test]
+fn test_short_circuit() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+
+    let engine = Engine::new().strict(true).register_fn("touch", move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Ok(Value::Boolean(true))
+    });
+
+    let eval = |input: &str| engine.evaluate(input).unwrap();
+
+    assert_eq!(eval("false && touch(1)"), Value::Boolean(false));
+    assert_eq!(eval("true || touch(1)"), Value::Boolean(true));
+    assert_eq!(eval("7 ?? touch(1)"), Value::Integer(7));
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    assert_eq!(eval("true && touch(1)"), Value::Boolean(true));
+    assert_eq!(eval("false || touch(1)"), Value::Boolean(true));
+    assert_eq!(eval("null ?? touch(1)"), Value::Boolean(true));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    // Right operands that would fail are never evaluated
+    assert_eq!(eval("false && missing"), Value::Boolean(false));
+    assert_eq!(
+        eval("{l = []; length(l) > 0 && l[0] == 1}"),
+        Value::Boolean(false)
+    );
+
+    assert_eq!(
+        eval(r#"{m = ["a": 1]; m["b"] ?? m["a"] ?? 0}"#),
+        Value::Integer(1)
+    );
+    assert_eq!(eval("null ?? null ?? 1 + 2"), Value::Integer(3));
+    assert_eq!(eval("false ?? true"), Value::Boolean(false));
+    assert_eq!(eval("null ?? false || true"), Value::Boolean(true));
+
+    // Operands of logical operators must be booleans
+    assert!(matches!(
+        engine_error(&engine, "true && 1").kind,
+        RuntimeErrorKind::TypeMismatch { .. }
+    ));
+
+    assert_eq!(formatted("a??b||c", true), "a ?? b || c");
+}
+
+#[test]
+fn test_structural_comparison() {
+    let truth = Value::Boolean(true);
+
+    assert_eq!(eval(r#""a" == "a""#), truth);
+    assert_eq!(eval("null == null"), truth);
+    assert_eq!(eval("[1, 2] == [1, 2]"), truth);
+    assert_eq!(eval(r#"["a": 1, "b": [2]] == ["a": 1.0, "b": [2]]"#), truth);
+    assert_eq!(eval("[1, 2] != [2, 1]"), truth);
+    assert_eq!(eval(r#"5 != "5""#), truth);
+    assert_eq!(eval("null != false"), truth);
+    assert_eq!(eval("1 == 1.0"), truth);
+    assert_eq!(eval("1 < 1.5"), truth);
+    assert_eq!(eval("-2 > -2.5"), truth);
+
+    // Integers and floats are compared exactly, even beyond 2^53
+    assert_eq!(
+        eval(
+            "[9007199254740993 == 9007199254740992.0, 9007199254740992.0 == 9007199254740992, \
+             9007199254740993 > 9007199254740992.0, 9223372036854775807 < 9223372036854775807.0]"
+        ),
+        eval("[false, true, true, true]")
+    );
+    assert_eq!(
+        eval("sort_by([9007199254740993, 9007199254740992.0, 9007199254740992], |x| x)"),
+        eval("[9007199254740992.0, 9007199254740992, 9007199254740993]")
+    );
+    assert_eq!(
+        eval(r#"{mode = "prod"; ? mode == "prod" : "p" | "d"}"#),
+        Value::String("p".to_string())
+    );
+
+    assert_eq!(eval(r#""apple" < "banana""#), truth);
+    assert_eq!(eval(r#""b" >= "abc""#), truth);
+    assert_eq!(eval("[1, 2] < [1, 3]"), truth);
+    assert_eq!(eval("[1, 2] < [1, 2, 0]"), truth);
+    assert_eq!(eval(r#"[["a"]] <= [["a"]]"#), truth);
+
+    assert_eq!(
+        eval(r#""con" + "cat""#),
+        Value::String("concat".to_string())
+    );
+    assert_eq!(eval("[1] + [2, 3]"), eval("[1, 2, 3]"));
+    assert_eq!(
+        eval(r#"["host": "localhost", "port": 80] + ["port": 8080]"#),
+        eval(r#"["host": "localhost", "port": 8080]"#)
+    );
+
+    assert_eq!(
+        error_kind(r#"[1] < ["a"]"#),
+        RuntimeErrorKind::NotComparable {
+            lhs: "integer",
+            rhs: "string"
+        }
+    );
+    assert_eq!(
+        error_kind("null < 1"),
+        RuntimeErrorKind::NotComparable {
+            lhs: "null",
+            rhs: "integer"
+        }
+    );
+    assert_eq!(
+        error_kind(r#""port" + 80"#),
+        RuntimeErrorKind::TypeMismatch {
+            expected: "string",
+            found: "integer"
+        }
+    );
+}
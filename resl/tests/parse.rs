@@ -0,0 +1,68 @@
+use resl::{ExprKind, NameKind, parse};
+
+fn names(input: &str) -> Vec<(NameKind, String, &str)> {
+    parse(input)
+        .unwrap()
+        .names()
+        .map(|name| {
+            (
+                name.kind,
+                name.text.clone(),
+                &input[name.span.start()..name.span.end()],
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_names() {
+    assert_eq!(
+        names(r#"{port = 80; add = |a, b = 1, ...rest| a + b; ["port": port]}"#),
+        [
+            (NameKind::Binding, "port".to_string(), "port"),
+            (NameKind::Binding, "add".to_string(), "add"),
+            (NameKind::Parameter, "a".to_string(), "a"),
+            (NameKind::Parameter, "b".to_string(), "b"),
+            (NameKind::Parameter, "rest".to_string(), "rest"),
+            (NameKind::Key, "port".to_string(), r#""port""#),
+        ]
+    );
+
+    assert_eq!(
+        names(r#"[1, 2] > (i, x): x"#),
+        [
+            (NameKind::Parameter, "i".to_string(), "i"),
+            (NameKind::Parameter, "x".to_string(), "x"),
+        ]
+    );
+
+    assert_eq!(
+        names(r#"match ["a": 1] { ["a": n] => n, [first, ...others] => first, _ => 0 }"#),
+        [
+            (NameKind::Key, "a".to_string(), r#""a""#),
+            (NameKind::Key, "a".to_string(), r#""a""#),
+            (NameKind::Parameter, "n".to_string(), "n"),
+            (NameKind::Parameter, "first".to_string(), "first"),
+            (NameKind::Parameter, "others".to_string(), "others"),
+        ]
+    );
+
+    // Strings in lists are not keys
+    assert_eq!(names(r#"["a", "b"]"#), []);
+}
+
+#[test]
+fn test_expr_spans() {
+    let input = "{x = [1, 2]; x[0] + f(x, 3)}";
+    let document = parse(input).unwrap();
+
+    let spans = document
+        .exprs()
+        .into_iter()
+        .filter(|expr| matches!(expr.kind(), ExprKind::Int(_)))
+        .map(|expr| &input[expr.span().start()..expr.span().end()])
+        .collect::<Vec<_>>();
+    assert_eq!(spans, ["1", "2", "0", "3"]);
+
+    assert!(parse("{x = 1; }").is_err());
+}
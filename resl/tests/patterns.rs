@@ -0,0 +1,96 @@
+mod common;
+
+use common::{evaluated, formatted, located_error};
+use resl::{RuntimeErrorKind, Value, evaluate};
+
+#[test]
+fn test_match() {
+    let replicas = |env: &str| {
+        evaluated(&format!(
+            r#"{{env = "{env}"; match env {{ "prod" => 3, "staging" => 2, _ => 1 }}}}"#
+        ))
+    };
+    assert_eq!(replicas("prod"), "3");
+    assert_eq!(replicas("staging"), "2");
+    assert_eq!(replicas("dev"), "1");
+
+    // Literals match like `==`, and the first fitting arm wins
+    assert_eq!(
+        evaluated(
+            r#"[1.0, null, true] > (i, v) : match v { 1 => "one", null => "null", _ => "other" }"#
+        ),
+        r#"["one", "null", "other"]"#
+    );
+    assert_eq!(evaluated("match 7 { n => n * 2, _ => 0 }"), "14");
+
+    // List patterns with rest and nested map patterns
+    assert_eq!(
+        evaluated(
+            "[[], [1], [1, 2, 3]] > (i, l) : match l { [] => 0, [x] => x, [x, ...rest] => rest }"
+        ),
+        "[0, 1, [2, 3]]"
+    );
+    assert_eq!(
+        evaluated(r#"match ["db": ["port": 5432, "ssl": true]] { ["db": ["port": p]] => p }"#),
+        "5432"
+    );
+    assert_eq!(
+        evaluated(r#"match ["host": "a"] { ["host": h, "port": p] => p, ["host": h] => h }"#),
+        r#""a""#
+    );
+
+    // Guards fall through to the next arm, and bindings stay within their arm
+    assert_eq!(
+        evaluated("{n = 5; f = |x| match x { n ? n < 0 => -n, _ => n }; [f(-2), f(3)]}"),
+        "[2, 5]"
+    );
+
+    // `match` remains usable as a name
+    assert_eq!(evaluated("{match = [1, 2]; match[1] + length(match)}"), "4");
+
+    assert_eq!(
+        located_error(r#"{env = "qa"; match env { "prod" => 1 }}"#),
+        (
+            RuntimeErrorKind::NoMatch("\"qa\"".to_string()),
+            "env".to_string()
+        )
+    );
+    assert_eq!(
+        located_error("match 1 { x ? x => 1, _ => 2 }"),
+        (
+            RuntimeErrorKind::TypeMismatch {
+                expected: "boolean",
+                found: "integer"
+            },
+            "x".to_string()
+        )
+    );
+    assert_eq!(evaluate("match 1 { 2 => 2 }").unwrap(), Value::Null);
+
+    for input in [
+        "match 1 {}",
+        "match 1 { 1 }",
+        "match 1 { [a, a] => a }",
+        "match 1 { [...r, a] => a }",
+        r#"match 1 { "${a}" => 1 }"#,
+    ] {
+        assert!(evaluate(input).is_err(), "{input} should not parse");
+    }
+
+    let input = r#"{x=1;match x{1=>"a",[a,...b]?a>0=>b,["k":v]=>v,_=>null}}"#;
+
+    assert_eq!(formatted(input, false), input);
+
+    assert_eq!(
+        formatted(input, true),
+        r#"{
+    x = 1;
+    match x {
+        1 => "a",
+        [a, ...b] ? a > 0 => b,
+        ["k": v] => v,
+        _ => null
+    }
+}"#
+    );
+}
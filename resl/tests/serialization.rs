@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use resl::{Error, Value, evaluate, from_str, from_value, to_string, to_string_pretty, to_value};
+use serde::{Deserialize, Serialize};
+
+#[test]
+fn test_deserialize() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Level {
+        Info,
+        Warn,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Target {
+        File(String),
+        Socket { host: String, port: u16 },
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Config {
+        name: String,
+        level: Level,
+        ratio: f64,
+        retries: Option<u8>,
+        tags: Vec<String>,
+        pair: (i64, bool),
+        targets: Vec<Target>,
+        limits: HashMap<String, u32>,
+    }
+
+    let config: Config = from_str(
+        r#"
+{
+    name = "service";
+    ["name": name, "level": "warn", "ratio": 2, "retries": null,
+     "tags": ["a", "b"], "pair": [1, true],
+     "targets": [["File": "out.log"], ["Socket": ["host": "localhost", "port": 8080]]],
+     "limits": ["cpu": 4]]
+}
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config,
+        Config {
+            name: "service".into(),
+            level: Level::Warn,
+            ratio: 2.0,
+            retries: None,
+            tags: vec!["a".into(), "b".into()],
+            pair: (1, true),
+            targets: vec![
+                Target::File("out.log".into()),
+                Target::Socket {
+                    host: "localhost".into(),
+                    port: 8080
+                }
+            ],
+            limits: HashMap::from([("cpu".into(), 4)]),
+        }
+    );
+    assert_eq!(
+        from_value::<Level>(Value::String("info".into())),
+        Ok(Level::Info)
+    );
+
+    let deserialize_error = |input: &str| match from_str::<Vec<Config>>(input) {
+        Err(Error::Deserialize(err)) => err,
+        other => panic!("Expected deserialization error for {input:?}, got {other:?}"),
+    };
+
+    let err = deserialize_error(r#"[["name": 1]]"#);
+    assert_eq!(err.path, "[0].name");
+
+    let err = deserialize_error(r#"[["name": "x"]]"#);
+    assert_eq!(err.path, "[0]");
+    assert_eq!(err.message, "missing field `level`");
+
+    let err = deserialize_error(r#"[["name": "x", "level": "info", "ratio": 1, "retries": 300]]"#);
+    assert_eq!(err.path, "[0].retries");
+
+    let err = deserialize_error(r#"[["targets": [["Socket": ["host": "h", "port": -1]]]]]"#);
+    assert_eq!(err.path, "[0].targets[0].Socket.port");
+
+    let limits = Value::Map([("max memory".into(), Value::String("1".into()))].into());
+    let err = from_value::<HashMap<String, u32>>(limits).unwrap_err();
+    assert_eq!(err.path, r#"["max memory"]"#);
+    assert_eq!(
+        err.to_string(),
+        format!("{} at `{}`", err.message, err.path)
+    );
+}
+
+#[test]
+fn test_serialize() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Target {
+        Stdout,
+        File(String),
+        Socket { host: String, port: u16 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        name: String,
+        pair: (i64, bool),
+        ratio: f64,
+        retries: Option<u8>,
+        targets: Vec<Target>,
+    }
+
+    let config = Config {
+        name: "service".into(),
+        pair: (-1, true),
+        ratio: 2.0,
+        retries: None,
+        targets: vec![
+            Target::Stdout,
+            Target::File("out.log".into()),
+            Target::Socket {
+                host: "localhost".into(),
+                port: 8080,
+            },
+        ],
+    };
+
+    let compact = to_string(&config).unwrap();
+    assert_eq!(
+        compact,
+        r#"["name": "service", "pair": [-1, true], "ratio": 2.0, "retries": null, "targets": ["Stdout", ["File": "out.log"], ["Socket": ["host": "localhost", "port": 8080]]]]"#
+    );
+    assert_eq!(from_str::<Config>(&compact).unwrap(), config);
+
+    let pretty = to_string_pretty(&config).unwrap();
+    let mut expected = String::new();
+    to_value(&config)
+        .unwrap()
+        .write_formatted(&mut expected, true)
+        .unwrap();
+    assert_eq!(pretty, expected);
+    assert_eq!(from_str::<Config>(&pretty).unwrap(), config);
+
+    assert_eq!(
+        to_value(&std::collections::BTreeMap::from([(1, 'a')])).unwrap(),
+        evaluate(r#"["1": "a"]"#).unwrap()
+    );
+    assert!(to_string(&(u64::MAX, 0)).is_err());
+    assert!(to_string(&HashMap::from([((), 0)])).is_err());
+
+    // Empty maps and structs are written as `[]`, which evaluates to a list
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Empty {}
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Limits {
+        limits: HashMap<String, u32>,
+        extra: Empty,
+        #[serde(default)]
+        nested: HashMap<String, HashMap<String, u32>>,
+    }
+
+    let limits = Limits {
+        limits: HashMap::new(),
+        extra: Empty {},
+        nested: HashMap::from([("cpu".into(), HashMap::new())]),
+    };
+    let source = to_string(&limits).unwrap();
+    assert!(source.contains(r#""limits": []"#));
+    assert_eq!(from_str::<Limits>(&source).unwrap(), limits);
+    assert_eq!(
+        from_str::<Limits>(&to_string_pretty(&limits).unwrap()).unwrap(),
+        limits
+    );
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::{eval, formatted, runtime_error};
+use resl::{RuntimeErrorKind, Value, evaluate};
+
+#[test]
+fn test_interpolation() {
+    let input = r#"{
+    host = "localhost";
+    port = 8080;
+    tags = ["a", "b"];
+    ["price": "$5 \${x}", "tags": "${tags} ${null} ${1.5}", "url": "http://${host}:${port}/"]
+}"#;
+
+    let value = eval(input);
+    assert_eq!(
+        value.to_string(),
+        r#"["price": "$5 \${x}", "tags": "[\"a\", \"b\"] null 1.5", "url": "http://localhost:8080/"]"#
+    );
+
+    // Embedded expressions may contain strings and nested interpolation
+    assert_eq!(
+        eval(r#""${ "inner ${1 + 1}" }!""#),
+        Value::String("inner 2!".into())
+    );
+    assert_eq!(eval(r#"to_str("text")"#), Value::String("text".into()));
+    assert!(evaluate(r#""${port""#).is_err());
+
+    let err = runtime_error(r#""id-${missing}""#);
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::UnknownIdentifier("missing".into())
+    );
+
+    let input =
+        r#"{port = 80; "http://${ ? (port > 0) : "x" | "y" }:${[port]} \${raw} $$${port}"}"#;
+    let compact = formatted(input, false);
+    assert_eq!(
+        compact,
+        r#"{port=80;"http://${?(port>0):"x"|"y"}:${[port]} \${raw} $$${port}"}"#
+    );
+
+    assert_eq!(formatted(&compact, false), compact);
+    assert_eq!(
+        eval(&compact),
+        Value::String("http://x:[80] ${raw} $$80".into())
+    );
+}
+
+#[test]
+fn test_strings() {
+    let string = |s: &str| Value::String(s.to_string());
+
+    assert_eq!(
+        eval(r#""tab\there\r\n\0 \u{e9}\u{1F600} \$""#),
+        string("tab\there\r\n\0 \u{e9}\u{1F600} $")
+    );
+    assert_eq!(eval(r##"r"C:\dir\${x}""##), string("C:\\dir\\${x}"));
+    assert_eq!(eval(r###"r#"say "hi""#"###), string("say \"hi\""));
+    assert_eq!(
+        eval("{name = \"web\"; \"\"\"\n    [${name}]\n      \"quoted\"\n\n    \"\"\"}"),
+        string("[web]\n  \"quoted\"\n")
+    );
+    assert_eq!(eval("\"\"\"\r\n  a\r\n  \"\"\""), string("a"));
+
+    for input in [
+        r#""\q""#,
+        r#""\u{110000}""#,
+        r#"r"open"#,
+        "\"\"\" text\n\"\"\"",
+        "\"\"\"\n  a\n b\n  \"\"\"",
+    ] {
+        assert!(
+            evaluate(input).is_err(),
+            "Expected a parse error for {input:?}"
+        );
+    }
+
+    // Formatted strings evaluate back to themselves
+    let value = eval(r#"["a\tb": "line\nbreak \u{7} ${1}\${x}", "r": r"\"]"#);
+    assert_eq!(
+        value.to_string(),
+        r#"["a\tb": "line\nbreak \u{7} 1\${x}", "r": "\\"]"#
+    );
+    assert_eq!(eval(&value.to_string()), value);
+
+    // Raw and multi-line string literals are formatted as written
+    for input in [
+        r###"[r#"say "hi""#, r"C:\dir", "a\tb"]"###,
+        r#"[r"k": 1, "v": r"\"]"#,
+        "\"\"\"\n  a \"${b /* c */}\"\n  \"\"\"",
+        "match x{r\"a\"=>1,_=>2}",
+    ] {
+        assert_eq!(formatted(input, false), input);
+    }
+}